@@ -0,0 +1,218 @@
+use crate::discovery::open_repository;
+use crate::error::GitError;
+use crate::git_operations::{fetch, RefUpdate, RepoInfo};
+use crate::progress::ProgressReporter;
+use crate::ssh::host_from_url;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the scheduler looks for repositories that are due for a fetch.
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Shortest interval accepted from the settings, remotes should not be polled harder.
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AutoFetchSettings {
+    pub enabled: bool,
+    pub interval_secs: u64,
+}
+
+/// Off until the user turns it on, fetching in the background talks to remotes unasked.
+impl Default for AutoFetchSettings {
+    fn default() -> Self {
+        AutoFetchSettings {
+            enabled: false,
+            interval_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteRefsChangedEvent {
+    pub path: String,
+    pub updates: Vec<RefUpdate>,
+    pub repo_info: Option<RepoInfo>,
+}
+
+/// When each repository is next fetched. Every fetch is pushed back by up to a fifth of the
+/// interval at random, so repositories added together do not all hit the network together.
+#[derive(Default)]
+pub struct AutoFetchSchedule {
+    next_due: HashMap<String, Instant>,
+    random: RandomState,
+}
+
+impl AutoFetchSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The repositories of `paths` whose fetch is due at `now`, which are rescheduled one
+    /// interval later. Repositories seen for the first time are due within the first
+    /// jitter window rather than immediately, the app just started or they were just added.
+    pub fn due(&mut self, paths: &[String], interval: Duration, now: Instant) -> Vec<String> {
+        self.next_due.retain(|path, _| paths.contains(path));
+
+        let mut due = Vec::new();
+        for path in paths {
+            let jitter = self.jitter(path, interval);
+            let next = self.next_due.entry(path.clone()).or_insert(now + jitter);
+            if *next <= now {
+                *next = now + interval + jitter;
+                due.push(path.clone());
+            }
+        }
+        due
+    }
+
+    fn jitter(&self, path: &str, interval: Duration) -> Duration {
+        let window = (interval.as_millis() / 5).max(1) as u64;
+        let seed = self.random.hash_one((path, Instant::now()));
+        Duration::from_millis(seed % window)
+    }
+}
+
+/// Checks every `TICK_INTERVAL` which repositories are due and hands them to `fetch` one
+/// at a time, on a thread of its own that lives as long as the app. `repositories` is
+/// asked for the current settings and repositories on each tick, so changes apply
+/// without a restart.
+pub fn start<R, F>(repositories: R, fetch: F)
+where
+    R: Fn() -> (AutoFetchSettings, Vec<String>) + Send + 'static,
+    F: Fn(&str) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut schedule = AutoFetchSchedule::new();
+        loop {
+            thread::sleep(TICK_INTERVAL);
+            let (settings, paths) = repositories();
+            if !settings.enabled {
+                continue;
+            }
+            let interval = Duration::from_secs(settings.interval_secs.max(MIN_INTERVAL_SECS));
+            for path in schedule.due(&paths, interval, Instant::now()) {
+                fetch(&path);
+            }
+        }
+    });
+}
+
+/// Fetches all remotes of a repository without asking the user anything, and reports the
+/// remote-tracking refs that moved. Repositories whose remote hosts cannot be resolved
+/// are skipped, the machine is most likely offline.
+pub fn fetch_in_background(
+    path: &str,
+    progress: &ProgressReporter,
+) -> Result<Option<RemoteRefsChangedEvent>, GitError> {
+    let repo = open_repository(path)?;
+    if !remotes_reachable(&repo) {
+        println!("[Rust AutoFetch] Remotes of {} unreachable, skipping", path);
+        return Ok(None);
+    }
+    drop(repo);
+
+    let result = fetch(path, None, None, None, Some(progress))?;
+    if result.updates.is_empty() {
+        return Ok(None);
+    }
+    println!(
+        "[Rust AutoFetch] {} remote-tracking refs moved in {}",
+        result.updates.len(),
+        path
+    );
+    Ok(Some(RemoteRefsChangedEvent {
+        path: path.to_string(),
+        updates: result.updates,
+        repo_info: result.repo_info,
+    }))
+}
+
+/// Whether the host of at least one remote resolves. Remotes on the local filesystem always count.
+fn remotes_reachable(repo: &Repository) -> bool {
+    let Ok(names) = repo.remotes() else {
+        return false;
+    };
+    names.iter().flatten().any(|name| {
+        let Some(url) = repo
+            .find_remote(name)
+            .ok()
+            .and_then(|r| r.url().map(str::to_string))
+        else {
+            return false;
+        };
+        if url.starts_with("file://") || Path::new(&url).exists() {
+            return true;
+        }
+        host_from_url(&url).is_some_and(|host| (host.as_str(), 0).to_socket_addrs().is_ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(600);
+    const JITTER_WINDOW: Duration = Duration::from_secs(120);
+
+    fn paths(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn new_repositories_are_due_within_the_jitter_window() {
+        let mut schedule = AutoFetchSchedule::new();
+        let start = Instant::now();
+        let repos = paths(&["/a", "/b", "/c"]);
+
+        let early = schedule.due(&repos, INTERVAL, start);
+        let mut due = schedule.due(&repos, INTERVAL, start + JITTER_WINDOW);
+        due.extend(early);
+        due.sort();
+        assert_eq!(due, repos);
+    }
+
+    #[test]
+    fn fetched_repositories_wait_one_interval() {
+        let mut schedule = AutoFetchSchedule::new();
+        let start = Instant::now();
+        let repos = paths(&["/a"]);
+
+        // Due right away only when the jitter happened to be zero
+        let fetched = if schedule.due(&repos, INTERVAL, start).is_empty() {
+            assert_eq!(schedule.due(&repos, INTERVAL, start + JITTER_WINDOW), repos);
+            start + JITTER_WINDOW
+        } else {
+            start
+        };
+        assert!(schedule.due(&repos, INTERVAL, fetched).is_empty());
+        assert!(schedule
+            .due(
+                &repos,
+                INTERVAL,
+                fetched + INTERVAL - Duration::from_secs(1)
+            )
+            .is_empty());
+        assert_eq!(
+            schedule.due(&repos, INTERVAL, fetched + INTERVAL + JITTER_WINDOW),
+            repos
+        );
+    }
+
+    #[test]
+    fn removed_repositories_are_forgotten() {
+        let mut schedule = AutoFetchSchedule::new();
+        let start = Instant::now();
+        schedule.due(&paths(&["/a", "/b"]), INTERVAL, start);
+        schedule.due(&paths(&["/a"]), INTERVAL, start);
+        assert!(!schedule.next_due.contains_key("/b"));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::discovery::open_repository;
+use crate::error::{ErrorKind, GitError};
+use crate::git_operations::{fetch, get_repo_info, pull};
+use crate::progress::ProgressReporter;
+use serde::{Deserialize, Serialize};
+
+/// How many repositories a batch works on at once unless the caller asks otherwise.
+pub const DEFAULT_BATCH_PARALLELISM: usize = 4;
+
+const BATCH_ACTIONS: [&str; 3] = ["fetch", "pull", "status"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchProgressEvent {
+    pub operation_id: String,
+    pub path: String,
+    pub state: String, // "running", "done", "failed"
+    pub completed: usize,
+    pub total: usize,
+    pub message: Option<String>,
+}
+
+/// One row of the summary table: the state of a repository after the action ran on it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchRepoResult {
+    pub path: String,
+    pub name: String,
+    pub current_branch: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub has_changes: bool,
+    pub outcome: Option<String>, // what the action did, e.g. "fast_forward" for a pull
+    pub error: Option<GitError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchSummary {
+    pub action: String, // "fetch", "pull", "status"
+    pub results: Vec<BatchRepoResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl BatchSummary {
+    pub fn new(action: &str, results: Vec<BatchRepoResult>) -> Self {
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        BatchSummary {
+            action: action.to_string(),
+            succeeded: results.len() - failed,
+            failed,
+            results,
+        }
+    }
+}
+
+pub fn check_action(action: &str) -> Result<(), GitError> {
+    if BATCH_ACTIONS.contains(&action) {
+        Ok(())
+    } else {
+        Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unknown batch action {}, expected one of {}",
+                action,
+                BATCH_ACTIONS.join(", ")
+            ),
+        ))
+    }
+}
+
+/// Runs one action on one repository. Pulls only fast-forward, a batch never leaves
+/// merge conflicts behind in repositories the user is not looking at.
+pub fn run_action(
+    path: &str,
+    action: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<Option<String>, GitError> {
+    check_action(action)?;
+    match action {
+        "fetch" => {
+            let result = fetch(path, None, None, None, progress)?;
+            Ok(Some(match result.updates.len() {
+                0 => "up_to_date".to_string(),
+                1 => "1 ref updated".to_string(),
+                n => format!("{} refs updated", n),
+            }))
+        }
+        "pull" => pull(path, Some("ff-only"), progress).map(|result| Some(result.status)),
+        _ => Ok(None),
+    }
+}
+
+/// Builds the summary row of a repository from the outcome of its action. The
+/// ahead/behind counts and dirty state are read afresh after the action, bare
+/// repositories have none of them.
+pub fn repo_result(path: &str, outcome: Result<Option<String>, GitError>) -> BatchRepoResult {
+    let info = open_repository(path).and_then(|repo| get_repo_info(&repo, path));
+    let (outcome, error) = match (outcome, &info) {
+        (Ok(outcome), Ok(_)) => (outcome, None),
+        (Err(e), _) => (None, Some(e)),
+        (Ok(outcome), Err(e)) if e.kind == ErrorKind::BareRepository => (outcome, None),
+        (Ok(outcome), Err(e)) => (outcome, Some(e.clone())),
+    };
+
+    match info {
+        Ok(info) => BatchRepoResult {
+            path: path.to_string(),
+            name: info.name,
+            current_branch: Some(info.current_branch),
+            ahead: info.ahead,
+            behind: info.behind,
+            has_changes: info.has_changes,
+            outcome,
+            error,
+        },
+        Err(_) => BatchRepoResult {
+            path: path.to_string(),
+            name: std::path::Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string()),
+            current_branch: None,
+            ahead: 0,
+            behind: 0,
+            has_changes: false,
+            outcome,
+            error,
+        },
+    }
+}
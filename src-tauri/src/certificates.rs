@@ -0,0 +1,505 @@
+use crate::error::GitError;
+use crate::prompts::PromptBroker;
+use crate::ssh::{home_dir, wildcard_match};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use git2::cert::Cert;
+use git2::{CertificateCheckStatus, ErrorClass, ErrorCode};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HostKeyRequest {
+    pub request_id: String,
+    pub operation_id: String,
+    pub host: String,
+    pub key_type: String,    // as written in known_hosts, e.g. "ssh-ed25519"
+    pub fingerprint: String, // "SHA256:..." as printed by ssh
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HostKeyResponse {
+    pub decision: String, // "accept_once", "accept_always", "reject"
+}
+
+/// Host key prompts waiting for the frontend, answered by `respond_host_key_cmd`.
+pub type HostKeyBroker = PromptBroker<HostKeyResponse>;
+
+pub type HostKeyPromptFn = Box<dyn Fn(&HostKeyRequest) -> Option<HostKeyResponse> + Send + Sync>;
+
+/// What `~/.ssh/known_hosts` says about the key a host presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnownHost {
+    Known,
+    Unknown,
+    /// The host is listed with another key of the same type
+    Changed,
+    /// The key is listed under `@revoked`
+    Revoked,
+}
+
+/// Host keys accepted for the rest of the session without being written to known_hosts,
+/// as (host, fingerprint) pairs.
+#[derive(Clone, Default)]
+pub struct HostKeyTrust {
+    accepted: Arc<Mutex<HashSet<(String, String)>>>,
+}
+
+impl HostKeyTrust {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_trusted(&self, host: &str, fingerprint: &str) -> bool {
+        self.lock()
+            .contains(&(host.to_string(), fingerprint.to_string()))
+    }
+
+    pub fn trust(&self, host: &str, fingerprint: &str) {
+        self.lock()
+            .insert((host.to_string(), fingerprint.to_string()));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<(String, String)>> {
+        match self.accepted.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Answers the libgit2 `certificate_check` callback of one operation. SSH host keys are
+/// checked against `~/.ssh/known_hosts`, unknown ones are shown to the user and changed
+/// ones refused; TLS certificates are left to libgit2, which validates them against the CA bundle.
+pub struct HostKeyVerifier {
+    trust: HostKeyTrust,
+    prompt: HostKeyPromptFn,
+}
+
+impl HostKeyVerifier {
+    pub fn new(trust: HostKeyTrust, prompt: HostKeyPromptFn) -> Self {
+        HostKeyVerifier { trust, prompt }
+    }
+
+    /// Checks the host key `host` presented on `port`, which is 22 unless the remote URL
+    /// names another one. libgit2 only passes the host name to the callback.
+    pub fn check(
+        &self,
+        cert: &Cert<'_>,
+        host: &str,
+        port: Option<u16>,
+        operation_id: &str,
+    ) -> Result<CertificateCheckStatus, git2::Error> {
+        let Some(hostkey) = cert.as_hostkey() else {
+            return Ok(CertificateCheckStatus::CertificatePassthrough);
+        };
+        let (Some(key), Some(key_type), Some(hash)) = (
+            hostkey.hostkey(),
+            hostkey.hostkey_type(),
+            hostkey.hash_sha256(),
+        ) else {
+            // Without the raw key libgit2 has to do the known_hosts check itself
+            return Ok(CertificateCheckStatus::CertificatePassthrough);
+        };
+        let key_type = key_type.name();
+        let fingerprint = format!("SHA256:{}", STANDARD_NO_PAD.encode(hash));
+        let name = known_hosts_name(host, port);
+
+        let known_hosts = known_hosts_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        match check_known_hosts(&known_hosts, &name, key_type, key) {
+            KnownHost::Known => return Ok(CertificateCheckStatus::CertificateOk),
+            KnownHost::Revoked => {
+                return Err(host_key_error(&format!(
+                    "The host key of {} ({}) has been revoked",
+                    name, fingerprint
+                )))
+            }
+            // Like ssh, never offer to accept a key that replaced a known one
+            KnownHost::Changed => {
+                return Err(host_key_error(&format!(
+                    "The host key of {} changed to {}. Someone may be intercepting the \
+                     connection; if the key was replaced on purpose, remove the old one from {}",
+                    name,
+                    fingerprint,
+                    known_hosts_path()
+                        .map_or("known_hosts".to_string(), |path| path.display().to_string())
+                )))
+            }
+            KnownHost::Unknown if self.trust.is_trusted(&name, &fingerprint) => {
+                return Ok(CertificateCheckStatus::CertificateOk)
+            }
+            KnownHost::Unknown => {}
+        }
+
+        println!(
+            "[Rust Certificates] Asking about unknown host key {} of {}",
+            fingerprint, name
+        );
+        let response = (self.prompt)(&HostKeyRequest {
+            request_id: String::new(),
+            operation_id: operation_id.to_string(),
+            host: name.clone(),
+            key_type: key_type.to_string(),
+            fingerprint: fingerprint.clone(),
+        });
+
+        match response.as_ref().map(|r| r.decision.as_str()) {
+            Some("accept_once") => {
+                self.trust.trust(&name, &fingerprint);
+                Ok(CertificateCheckStatus::CertificateOk)
+            }
+            Some("accept_always") => {
+                self.trust.trust(&name, &fingerprint);
+                if let Err(e) = remember_host_key(&name, key_type, key) {
+                    println!(
+                        "[Rust Certificates] Could not update known_hosts: {}",
+                        e.message
+                    );
+                }
+                Ok(CertificateCheckStatus::CertificateOk)
+            }
+            _ => Err(host_key_error(&format!(
+                "The host key of {} ({}) was not accepted",
+                name, fingerprint
+            ))),
+        }
+    }
+}
+
+/// The name a host is listed under in known_hosts: `host` on the default port and
+/// `[host]:port` on any other, as written by OpenSSH.
+pub fn known_hosts_name(host: &str, port: Option<u16>) -> String {
+    match port {
+        Some(port) if port != 22 => format!("[{}]:{}", host, port),
+        _ => host.to_string(),
+    }
+}
+
+fn host_key_error(message: &str) -> git2::Error {
+    git2::Error::new(ErrorCode::Certificate, ErrorClass::Ssh, message)
+}
+
+pub fn known_hosts_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+/// Looks `name` up in the contents of a known_hosts file, see `known_hosts_name`.
+pub fn check_known_hosts(contents: &str, name: &str, key_type: &str, key: &[u8]) -> KnownHost {
+    let mut status = KnownHost::Unknown;
+
+    for line in contents.lines() {
+        let Some(entry) = parse_known_host(line) else {
+            continue;
+        };
+        let same_key = entry.key_type == key_type && entry.key == key;
+        match entry.marker {
+            Some("@revoked") if same_key => return KnownHost::Revoked,
+            // Certificate authorities sign host certificates, which libgit2 does not send
+            Some(_) => continue,
+            None => {}
+        }
+        if !known_host_matches(entry.hosts, name) {
+            continue;
+        }
+        if same_key {
+            status = KnownHost::Known;
+        } else if entry.key_type == key_type && status == KnownHost::Unknown {
+            status = KnownHost::Changed;
+        }
+    }
+
+    status
+}
+
+/// Writes the key of `name` to `~/.ssh/known_hosts`, replacing the keys of the same type
+/// previously listed under it exactly. Wildcard entries also cover other hosts and are kept.
+pub fn remember_host_key(name: &str, key_type: &str, key: &[u8]) -> Result<(), GitError> {
+    let path = known_hosts_path().ok_or("No home directory to store known_hosts in")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let entry = format!("{} {} {}\n", name, key_type, STANDARD.encode(key));
+    let mut contents = String::with_capacity(existing.len() + entry.len());
+    let mut replacing = false;
+    for line in existing.lines() {
+        let replaced = parse_known_host(line).is_some_and(|entry| {
+            entry.marker.is_none() && entry.key_type == key_type && names_host(entry.hosts, name)
+        });
+        replacing |= replaced;
+        if !replaced {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+    }
+
+    if replacing {
+        // Rewrite through a file next to it, known_hosts is never left half written
+        contents.push_str(&entry);
+        let temp = path.with_file_name(format!(".known_hosts.{}.tmp", std::process::id()));
+        let written = (|| {
+            let mut file = std::fs::File::create(&temp)?;
+            file.write_all(contents.as_bytes())?;
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.sync_all()?;
+            std::fs::rename(&temp, &path)
+        })();
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e.into());
+        }
+    } else {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        if !existing.is_empty() && !existing.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+        file.write_all(entry.as_bytes())?;
+    }
+    println!(
+        "[Rust Certificates] Added host key of {} to known_hosts",
+        name
+    );
+    Ok(())
+}
+
+/// Points libgit2 at the CA bundle configured for git (`http.sslCAInfo`, `http.sslCAPath`,
+/// or `GIT_SSL_CAINFO` / `GIT_SSL_CAPATH`), for servers signed by a private CA.
+/// Must run before any network operation, the setting is global to the process.
+pub fn configure_ca_bundle() {
+    let config = git2::Config::open_default().ok();
+    let setting = |env: &str, key: &str| {
+        std::env::var(env)
+            .ok()
+            .or_else(|| {
+                config
+                    .as_ref()?
+                    .get_path(key)
+                    .ok()
+                    .map(|p| p.to_string_lossy().to_string())
+            })
+            .filter(|value| !value.is_empty())
+    };
+
+    if let Some(file) = setting("GIT_SSL_CAINFO", "http.sslCAInfo") {
+        // SAFETY: called once at startup, before any thread uses libgit2
+        match unsafe { git2::opts::set_ssl_cert_file(&file) } {
+            Ok(()) => println!("[Rust Certificates] Using CA bundle {}", file),
+            Err(e) => println!(
+                "[Rust Certificates] Could not use CA bundle {}: {}",
+                file, e
+            ),
+        }
+    }
+    if let Some(dir) = setting("GIT_SSL_CAPATH", "http.sslCAPath") {
+        // SAFETY: as above
+        match unsafe { git2::opts::set_ssl_cert_dir(&dir) } {
+            Ok(()) => println!("[Rust Certificates] Using CA directory {}", dir),
+            Err(e) => println!(
+                "[Rust Certificates] Could not use CA directory {}: {}",
+                dir, e
+            ),
+        }
+    }
+}
+
+struct KnownHostEntry<'a> {
+    marker: Option<&'a str>,
+    hosts: &'a str,
+    key_type: &'a str,
+    key: Vec<u8>,
+}
+
+fn parse_known_host(line: &str) -> Option<KnownHostEntry<'_>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let mut fields = line.split_whitespace();
+    let mut first = fields.next()?;
+    let marker = if first.starts_with('@') {
+        let marker = first;
+        first = fields.next()?;
+        Some(marker)
+    } else {
+        None
+    };
+    let key_type = fields.next()?;
+    let key = STANDARD.decode(fields.next()?).ok()?;
+    Some(KnownHostEntry {
+        marker,
+        hosts: first,
+        key_type,
+        key,
+    })
+}
+
+/// Matches the host field of a known_hosts line against a name from `known_hosts_name`: a
+/// hashed `|1|salt|hash` entry, or a comma-separated list of patterns where `!pattern`
+/// excludes hosts.
+fn known_host_matches(hosts: &str, name: &str) -> bool {
+    if let Some(hashed) = hosts.strip_prefix("|1|") {
+        return hashed_host_matches(hashed, name);
+    }
+
+    let mut matched = false;
+    for pattern in hosts.split(',') {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        if wildcard_match(pattern, name) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn names_host(hosts: &str, name: &str) -> bool {
+    match hosts.strip_prefix("|1|") {
+        Some(hashed) => hashed_host_matches(hashed, name),
+        None => hosts
+            .split(',')
+            .any(|pattern| pattern.eq_ignore_ascii_case(name)),
+    }
+}
+
+fn hashed_host_matches(hashed: &str, host: &str) -> bool {
+    let Some((salt, hash)) = hashed.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(host.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"server key";
+    const OTHER_KEY: &[u8] = b"another key";
+
+    fn line(hosts: &str, key: &[u8]) -> String {
+        format!("{} ssh-ed25519 {}\n", hosts, STANDARD.encode(key))
+    }
+
+    fn hashed(name: &str) -> String {
+        let salt = b"0123456789abcdefghij";
+        let mut mac = Hmac::<Sha1>::new_from_slice(salt).unwrap();
+        mac.update(name.as_bytes());
+        format!(
+            "|1|{}|{}",
+            STANDARD.encode(salt),
+            STANDARD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    #[test]
+    fn known_unknown_and_changed_keys() {
+        let contents = format!(
+            "# comment\n{}{}",
+            line("github.com,140.82.121.4", KEY),
+            line("*.example.com,!internal.example.com", KEY)
+        );
+        let check = |name: &str, key: &[u8]| check_known_hosts(&contents, name, "ssh-ed25519", key);
+
+        assert_eq!(check("github.com", KEY), KnownHost::Known);
+        assert_eq!(check("140.82.121.4", KEY), KnownHost::Known);
+        assert_eq!(check("github.com", OTHER_KEY), KnownHost::Changed);
+        assert_eq!(check("gitlab.com", KEY), KnownHost::Unknown);
+        assert_eq!(check("git.example.com", KEY), KnownHost::Known);
+        assert_eq!(check("internal.example.com", KEY), KnownHost::Unknown);
+
+        // A key of another type is not a change
+        assert_eq!(
+            check_known_hosts(&contents, "github.com", "ssh-rsa", OTHER_KEY),
+            KnownHost::Unknown
+        );
+
+        // Any line listing the key makes it known, whatever the order
+        let rotated = format!(
+            "{}{}",
+            line("github.com", OTHER_KEY),
+            line("github.com", KEY)
+        );
+        assert_eq!(
+            check_known_hosts(&rotated, "github.com", "ssh-ed25519", KEY),
+            KnownHost::Known
+        );
+    }
+
+    #[test]
+    fn revoked_keys_are_refused_for_every_host() {
+        let contents = format!("@revoked {}{}", line("*", KEY), line("github.com", KEY));
+        assert_eq!(
+            check_known_hosts(&contents, "github.com", "ssh-ed25519", KEY),
+            KnownHost::Revoked
+        );
+    }
+
+    #[test]
+    fn hashed_hosts_match_their_name_only() {
+        let contents = line(&hashed("github.com"), KEY);
+        let check = |name: &str, key: &[u8]| check_known_hosts(&contents, name, "ssh-ed25519", key);
+        assert_eq!(check("github.com", KEY), KnownHost::Known);
+        assert_eq!(check("github.com", OTHER_KEY), KnownHost::Changed);
+        assert_eq!(check("gitlab.com", KEY), KnownHost::Unknown);
+        assert!(names_host(&hashed("github.com"), "github.com"));
+    }
+
+    #[test]
+    fn non_default_ports_are_listed_as_bracketed_names() {
+        assert_eq!(known_hosts_name("example.com", None), "example.com");
+        assert_eq!(known_hosts_name("example.com", Some(22)), "example.com");
+        assert_eq!(
+            known_hosts_name("example.com", Some(2222)),
+            "[example.com]:2222"
+        );
+
+        let contents = format!(
+            "{}{}",
+            line("example.com", KEY),
+            line("[example.com]:2222", OTHER_KEY)
+        );
+        let check = |name: &str, key: &[u8]| check_known_hosts(&contents, name, "ssh-ed25519", key);
+        assert_eq!(check("example.com", KEY), KnownHost::Known);
+        assert_eq!(check("[example.com]:2222", OTHER_KEY), KnownHost::Known);
+        assert_eq!(check("[example.com]:2222", KEY), KnownHost::Changed);
+        assert_eq!(check("[example.com]:2200", KEY), KnownHost::Unknown);
+
+        let hashed_port = line(&hashed("[example.com]:2222"), KEY);
+        assert_eq!(
+            check_known_hosts(&hashed_port, "[example.com]:2222", "ssh-ed25519", KEY),
+            KnownHost::Known
+        );
+        assert_eq!(
+            check_known_hosts(&hashed_port, "example.com", "ssh-ed25519", KEY),
+            KnownHost::Unknown
+        );
+    }
+}
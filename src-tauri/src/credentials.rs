@@ -0,0 +1,389 @@
+use crate::progress::ProgressReporter;
+use crate::prompts::PromptBroker;
+use crate::ssh::{
+    host_from_url, identities_for_host, passphrase_unlocks, public_key_for, SshIdentity,
+};
+use git2::{Cred, CredentialType, ErrorClass, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The user is asked for a password at most this many times per operation, and for the
+/// passphrase of each SSH key at most this many times before the next key is tried.
+const MAX_CREDENTIAL_PROMPTS: u32 = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialRequest {
+    pub request_id: String,
+    pub operation_id: String,
+    pub url: String,
+    pub kind: String, // "userpass", "ssh_passphrase"
+    pub username: Option<String>,
+    pub key_path: Option<String>,
+    pub attempt: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialResponse {
+    pub username: Option<String>,
+    pub password: Option<String>, // token or password, or the passphrase of an SSH key
+}
+
+/// Username and password sent to a remote, remembered until the operation ends so the
+/// credential helper can be told whether they worked.
+#[derive(Debug, Clone)]
+pub struct OfferedCredentials {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+pub type CredentialPromptFn =
+    Box<dyn Fn(&CredentialRequest) -> Option<CredentialResponse> + Send + Sync>;
+
+/// Credential prompts waiting for the frontend, answered by `respond_credentials_cmd`.
+pub type CredentialBroker = PromptBroker<CredentialResponse>;
+
+/// Answers the libgit2 `credentials` callback of one operation: credentials given up-front
+/// first, then the SSH agent and key files or the configured `credential.helper`,
+/// then the user through the prompt.
+pub struct CredentialHandler<'a> {
+    progress: Option<&'a ProgressReporter>,
+    initial: Option<CredentialResponse>,
+    agent_tried: bool,
+    ssh_keys: Option<Vec<SshIdentity>>,
+    helper_tried: bool,
+    prompts: u32,
+    offered: bool,
+    rejected: bool,
+}
+
+impl<'a> CredentialHandler<'a> {
+    pub fn new(
+        progress: Option<&'a ProgressReporter>,
+        initial: Option<CredentialResponse>,
+    ) -> Self {
+        CredentialHandler {
+            progress,
+            initial,
+            agent_tried: false,
+            ssh_keys: None,
+            helper_tried: false,
+            prompts: 0,
+            offered: false,
+            rejected: false,
+        }
+    }
+
+    /// Whether the remote asked again after credentials were offered, which means it
+    /// refused them.
+    pub fn rejected(&self) -> bool {
+        self.rejected
+    }
+
+    pub fn credentials(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username_from_url.unwrap_or("git"));
+        }
+        self.rejected |= self.offered;
+        self.offered = true;
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username_from_url.unwrap_or("git");
+            let host = host_from_url(url).unwrap_or_default();
+            if self.ssh_keys.is_none() {
+                let settings = self.progress.map(|p| p.ssh_settings()).unwrap_or_default();
+                let (mut identities, identities_only) = identities_for_host(&host, user, &settings);
+                // Keys are taken from the back, one per attempt
+                identities.reverse();
+                self.ssh_keys = Some(identities);
+                self.agent_tried = identities_only;
+            }
+
+            if !self.agent_tried {
+                self.agent_tried = true;
+                return Cred::ssh_key_from_agent(user);
+            }
+
+            loop {
+                let identity = self
+                    .ssh_keys
+                    .as_mut()
+                    .and_then(|keys| keys.pop())
+                    .ok_or_else(|| auth_error(&format!("No SSH key was accepted by {}", host)))?;
+                let key = PathBuf::from(&identity.path);
+                let passphrase = if identity.encrypted {
+                    match self.key_passphrase(url, user, &identity)? {
+                        Some(passphrase) => Some(passphrase),
+                        None => continue,
+                    }
+                } else {
+                    None
+                };
+                println!(
+                    "[Rust Credentials] Offering SSH key {} to {}",
+                    identity.path, host
+                );
+                return Cred::ssh_key(
+                    user,
+                    public_key_for(&key).as_deref(),
+                    &key,
+                    passphrase.as_deref(),
+                );
+            }
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            // Being asked again means the remote refused what was sent last time
+            if let Some(rejected) = self.take_offered() {
+                credential_helper("reject", &rejected);
+            }
+
+            let response = match self.initial.take() {
+                Some(initial) => initial,
+                None => match self.helper_credentials(url, username_from_url) {
+                    Some(found) => found,
+                    None => {
+                        if self.prompts >= MAX_CREDENTIAL_PROMPTS {
+                            return Err(auth_error(&format!(
+                                "Authentication failed after {} attempts",
+                                self.prompts
+                            )));
+                        }
+                        self.prompts += 1;
+                        self.ask(url, "userpass", username_from_url, None, self.prompts)?
+                    }
+                },
+            };
+            let offered = OfferedCredentials {
+                url: url.to_string(),
+                username: response
+                    .username
+                    .as_deref()
+                    .or(username_from_url)
+                    .unwrap_or("git")
+                    .to_string(),
+                password: response.password.unwrap_or_default(),
+            };
+            let cred = Cred::userpass_plaintext(&offered.username, &offered.password);
+            if let Some(progress) = self.progress {
+                progress.set_offered_credentials(Some(offered));
+            }
+            return cred;
+        }
+
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+
+        Err(auth_error(
+            "The remote asked for an unsupported authentication method",
+        ))
+    }
+
+    fn helper_credentials(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+    ) -> Option<CredentialResponse> {
+        if self.helper_tried {
+            return None;
+        }
+        self.helper_tried = true;
+
+        // Without a prompt nobody is watching, the helper must not open a window either
+        let interactive = self
+            .progress
+            .is_some_and(|p| p.credential_prompt().is_some());
+        let (username, password) = credential_helper_fill(url, username_from_url, interactive)?;
+        println!(
+            "[Rust Credentials] Using credentials from credential.helper for {}",
+            url
+        );
+        Some(CredentialResponse {
+            username: Some(username),
+            password: Some(password),
+        })
+    }
+
+    fn take_offered(&self) -> Option<OfferedCredentials> {
+        let progress = self.progress?;
+        let offered = progress.offered_credentials();
+        progress.set_offered_credentials(None);
+        offered
+    }
+
+    /// Asks for the passphrase of an encrypted key until one decrypts it, up to
+    /// `MAX_CREDENTIAL_PROMPTS` times. `None` once they are used up, to move on to the next key.
+    fn key_passphrase(
+        &mut self,
+        url: &str,
+        user: &str,
+        identity: &SshIdentity,
+    ) -> Result<Option<String>, git2::Error> {
+        for attempt in 1..=MAX_CREDENTIAL_PROMPTS {
+            let passphrase = self
+                .ask(
+                    url,
+                    "ssh_passphrase",
+                    Some(user),
+                    Some(identity.path.clone()),
+                    attempt,
+                )?
+                .password
+                .unwrap_or_default();
+            match passphrase_unlocks(Path::new(&identity.path), &passphrase) {
+                Some(false) => println!(
+                    "[Rust Credentials] Wrong passphrase for SSH key {}",
+                    identity.path
+                ),
+                // Without ssh-keygen the key is offered and libssh2 tells
+                Some(true) | None => return Ok(Some(passphrase)),
+            }
+        }
+        println!(
+            "[Rust Credentials] Skipping SSH key {} after {} wrong passphrases",
+            identity.path, MAX_CREDENTIAL_PROMPTS
+        );
+        Ok(None)
+    }
+
+    /// Sends one request to the credential prompt. `attempt` counts the prompts for the same
+    /// credentials, the frontend says the previous answer was wrong when it is above 1.
+    fn ask(
+        &self,
+        url: &str,
+        kind: &str,
+        username: Option<&str>,
+        key_path: Option<String>,
+        attempt: u32,
+    ) -> Result<CredentialResponse, git2::Error> {
+        let progress = self
+            .progress
+            .ok_or_else(|| auth_error("Authentication required"))?;
+        let prompt = progress
+            .credential_prompt()
+            .ok_or_else(|| auth_error("Authentication required"))?;
+
+        prompt(&CredentialRequest {
+            request_id: String::new(),
+            operation_id: progress.operation_id().to_string(),
+            url: url.to_string(),
+            kind: kind.to_string(),
+            username: username.map(str::to_string),
+            key_path,
+            attempt,
+        })
+        .ok_or_else(|| {
+            git2::Error::new(
+                ErrorCode::User,
+                ErrorClass::Callback,
+                "Authentication cancelled",
+            )
+        })
+    }
+}
+
+fn auth_error(message: &str) -> git2::Error {
+    git2::Error::new(ErrorCode::Auth, ErrorClass::Callback, message)
+}
+
+/// Tells the configured `credential.helper` whether credentials worked, so newly entered
+/// ones are stored and rejected ones are forgotten.
+pub fn credential_helper(action: &str, credentials: &OfferedCredentials) {
+    if ![
+        &credentials.url,
+        &credentials.username,
+        &credentials.password,
+    ]
+    .iter()
+    .all(|value| is_protocol_safe(value))
+    {
+        println!(
+            "[Rust Credentials] Skipping credential {}, value would break the helper protocol",
+            action
+        );
+        return;
+    }
+    let input = format!(
+        "url={}\nusername={}\npassword={}\n\n",
+        credentials.url, credentials.username, credentials.password
+    );
+    match run_git_credential(action, &input, false) {
+        Some(_) => println!(
+            "[Rust Credentials] credential {} for {}",
+            action, credentials.url
+        ),
+        None => println!(
+            "[Rust Credentials] credential {} failed for {}",
+            action, credentials.url
+        ),
+    }
+}
+
+/// Looks up credentials for `url` through `git credential fill`, without letting git prompt.
+fn credential_helper_fill(
+    url: &str,
+    username: Option<&str>,
+    interactive: bool,
+) -> Option<(String, String)> {
+    if !is_protocol_safe(url) || !username.is_none_or(is_protocol_safe) {
+        return None;
+    }
+    let mut input = format!("url={}\n", url);
+    if let Some(username) = username {
+        input.push_str(&format!("username={}\n", username));
+    }
+    input.push('\n');
+
+    let output = run_git_credential("fill", &input, interactive)?;
+    let mut username = None;
+    let mut password = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix("username=") {
+            username = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("password=") {
+            password = Some(value.to_string());
+        }
+    }
+    Some((username?, password?))
+}
+
+/// Whether `value` can be sent as one `key=value` line of the `git credential` protocol. A
+/// newline in a URL would let it add lines of its own, like `host=` of another server.
+fn is_protocol_safe(value: &str) -> bool {
+    !value.contains(['\n', '\r', '\0'])
+}
+
+/// Runs `git credential <action>`. Helpers that can show a window of their own, like Git
+/// Credential Manager, are only allowed to when `interactive` is set.
+fn run_git_credential(action: &str, input: &str, interactive: bool) -> Option<String> {
+    let mut command = Command::new("git");
+    if !interactive {
+        command.env("GCM_INTERACTIVE", "never");
+    }
+    let mut child = command
+        .args(["credential", action])
+        // Never fall back to a terminal or askpass prompt, the app asks the user itself
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "")
+        .env("SSH_ASKPASS", "")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    child.stdin.take()?.write_all(input.as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
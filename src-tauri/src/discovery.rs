@@ -0,0 +1,148 @@
+use crate::error::{ErrorKind, GitError};
+use crate::git_operations::{get_repo_info, RepoInfo};
+use crate::ssh::home_dir;
+use git2::{ErrorCode, Repository};
+use std::path::{Path, PathBuf};
+
+/// How deep `scan_repositories` looks below the folder it was given by default.
+pub const DEFAULT_SCAN_DEPTH: usize = 4;
+
+/// Folders that hold dependencies or build output and never contain repositories worth importing.
+const SKIPPED_DIRS: [&str; 2] = ["node_modules", "target"];
+
+/// Opens the repository containing `path`, which may be any folder inside its working tree.
+/// Failures are reported with a kind the frontend can act on: a missing path, a folder
+/// outside any repository, a folder that cannot be read, or a repository owned by
+/// another user that git refuses to open until it is listed in `safe.directory`.
+pub fn open_repository(path: &str) -> Result<Repository, GitError> {
+    let dir = Path::new(path);
+    if !dir.exists() {
+        return Err(GitError::new(
+            ErrorKind::NotFound,
+            format!("{} does not exist", path),
+        ));
+    }
+    if let Err(e) = std::fs::read_dir(dir) {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            return Err(GitError::new(
+                ErrorKind::PermissionDenied,
+                format!("Permission denied reading {}", path),
+            ));
+        }
+    }
+
+    match Repository::discover(dir) {
+        Ok(repo) => Ok(repo),
+        Err(e) if e.code() == ErrorCode::Owner => {
+            let root = repository_root(path).unwrap_or_else(|| dir.to_path_buf());
+            Err(GitError {
+                message: format!(
+                    "{} is owned by another user. Git only opens it once it is trusted through safe.directory",
+                    root.display()
+                ),
+                ..GitError::from(e)
+            })
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => Err(GitError {
+            message: format!("{} is not inside a git repository", path),
+            ..GitError::from(e).with_kind(ErrorKind::NotARepository)
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The top of the repository containing `path`: the working tree root, or the git
+/// directory of a bare repository. Ownership is not checked here.
+pub fn repository_root(path: &str) -> Option<PathBuf> {
+    let git_dir = Repository::discover_path(path, &[] as &[&str]).ok()?;
+    let git_dir = git_dir.components().collect::<PathBuf>();
+    match git_dir.file_name() {
+        Some(name) if name == ".git" => git_dir.parent().map(Path::to_path_buf),
+        _ => Some(git_dir),
+    }
+}
+
+/// Trusts the repository containing `path` despite its ownership, by adding its root to
+/// `safe.directory` in the global git config, as `git config --global --add` would.
+/// Returns the path that was added.
+pub fn add_safe_directory(path: &str) -> Result<String, GitError> {
+    let root = repository_root(path).ok_or_else(|| {
+        GitError::new(
+            ErrorKind::NotARepository,
+            format!("{} is not inside a git repository", path),
+        )
+    })?;
+    // git compares safe.directory entries with forward slashes on every platform
+    let entry = root.to_string_lossy().replace('\\', "/");
+
+    let global = match git2::Config::find_global() {
+        Ok(file) => file,
+        Err(_) => home_dir()
+            .ok_or("No home directory to write the global git config to")?
+            .join(".gitconfig"),
+    };
+    let mut config = git2::Config::open(&global)?;
+    let already = config
+        .multivar("safe.directory", None)
+        .map(|mut entries| {
+            let mut found = false;
+            while let Some(Ok(existing)) = entries.next() {
+                found |= existing.value() == Some(entry.as_str());
+            }
+            found
+        })
+        .unwrap_or(false);
+    if !already {
+        // A pattern matching no existing value appends a new entry
+        config.set_multivar("safe.directory", "^$", &entry)?;
+        println!("[Rust Discovery] Added {} to safe.directory", entry);
+    }
+    Ok(entry)
+}
+
+/// Looks for repositories and linked worktrees in `path` and the folders below it, up to
+/// `max_depth` levels down. Folders inside a repository that was found are not searched,
+/// and bare repositories or ones that cannot be opened are left out.
+pub fn scan_repositories(path: &str, max_depth: usize) -> Result<Vec<RepoInfo>, GitError> {
+    let root = Path::new(path);
+    if !root.is_dir() {
+        return Err(GitError::new(
+            ErrorKind::NotFound,
+            format!("{} is not a folder", path),
+        ));
+    }
+
+    println!("[Rust Discovery] Scanning {} for repositories", path);
+    let mut found = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        // A .git folder is a repository, a .git file a linked worktree or submodule
+        if dir.join(".git").exists() {
+            let dir = dir.to_string_lossy().to_string();
+            match open_repository(&dir).and_then(|repo| get_repo_info(&repo, &dir)) {
+                Ok(info) => found.push(info),
+                Err(e) => println!("[Rust Discovery] Skipping {}: {}", dir, e.message),
+            }
+            continue;
+        }
+        if depth >= max_depth {
+            continue;
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            // Symlinks are not followed, they can loop back up the tree
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let name = entry.file_name();
+            if is_dir && !SKIPPED_DIRS.iter().any(|skipped| name == *skipped) {
+                pending.push((entry.path(), depth + 1));
+            }
+        }
+    }
+
+    found.sort_by(|a, b| a.root.cmp(&b.root));
+    println!("[Rust Discovery] Found {} repositories", found.len());
+    Ok(found)
+}
@@ -0,0 +1,143 @@
+use git2::{ErrorClass, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    AuthRequired,
+    AuthFailed,
+    NotARepository,
+    BareRepository,
+    UnsafeRepository,
+    PermissionDenied,
+    NotFound,
+    UnbornBranch,
+    Conflicts,
+    UncommittedChanges,
+    NonFastForward,
+    LockedIndex,
+    Network,
+    Certificate,
+    Cancelled,
+    InvalidInput,
+    Other,
+}
+
+/// Error returned by every command, serialized to the frontend as
+/// `{ kind, message, class, code }` so it can react to the kind instead of the text.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub class: Option<String>, // libgit2 error class, e.g. "Net", "Http", "Index"
+    pub code: Option<String>,  // libgit2 error code, e.g. "Auth", "NotFastForward", "Locked"
+}
+
+impl GitError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        GitError {
+            kind,
+            message: message.into(),
+            class: None,
+            code: None,
+        }
+    }
+
+    pub fn other(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+
+    pub fn cancelled() -> Self {
+        Self::new(ErrorKind::Cancelled, "Operation cancelled")
+    }
+
+    /// Keeps the libgit2 class and code of the error but reports it as another kind,
+    /// for callers that know more about the failure than libgit2 does.
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        let kind = match (e.code(), e.class()) {
+            (ErrorCode::Auth, _) => ErrorKind::AuthRequired,
+            (ErrorCode::NotFound, ErrorClass::Repository) => ErrorKind::NotARepository,
+            (ErrorCode::BareRepo, _) => ErrorKind::BareRepository,
+            // The repository is owned by another user and not listed in safe.directory
+            (ErrorCode::Owner, _) => ErrorKind::UnsafeRepository,
+            (ErrorCode::NotFound, _) => ErrorKind::NotFound,
+            (ErrorCode::UnbornBranch, _) => ErrorKind::UnbornBranch,
+            // A checkout conflict means local changes would be overwritten
+            (ErrorCode::Conflict, ErrorClass::Checkout) => ErrorKind::UncommittedChanges,
+            (ErrorCode::Uncommitted | ErrorCode::IndexDirty, _) => ErrorKind::UncommittedChanges,
+            (ErrorCode::Conflict | ErrorCode::MergeConflict | ErrorCode::Unmerged, _) => {
+                ErrorKind::Conflicts
+            }
+            (ErrorCode::NotFastForward, _) => ErrorKind::NonFastForward,
+            (ErrorCode::Locked, _) => ErrorKind::LockedIndex,
+            (ErrorCode::User, _) => ErrorKind::Cancelled,
+            (ErrorCode::Timeout, _) => ErrorKind::Network,
+            (ErrorCode::Certificate, _) => ErrorKind::Certificate,
+            (ErrorCode::InvalidSpec | ErrorCode::Invalid | ErrorCode::Ambiguous, _) => {
+                ErrorKind::InvalidInput
+            }
+            (_, ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssh | ErrorClass::Ssl) => {
+                ErrorKind::Network
+            }
+            _ => ErrorKind::Other,
+        };
+
+        GitError {
+            kind,
+            message: e.message().to_string(),
+            class: Some(format!("{:?}", e.class())),
+            code: Some(format!("{:?}", e.code())),
+        }
+    }
+}
+
+impl From<std::io::Error> for GitError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                Self::new(ErrorKind::PermissionDenied, e.to_string())
+            }
+            _ => Self::other(e.to_string()),
+        }
+    }
+}
+
+impl From<String> for GitError {
+    fn from(message: String) -> Self {
+        Self::other(message)
+    }
+}
+
+impl From<&str> for GitError {
+    fn from(message: &str) -> Self {
+        Self::other(message)
+    }
+}
+
+impl From<serde_json::Error> for GitError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::other(e.to_string())
+    }
+}
+
+impl From<notify::Error> for GitError {
+    fn from(e: notify::Error) -> Self {
+        Self::other(e.to_string())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::error::GitError;
+use git2::Repository;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime;
+
+/// Runs git work on the blocking thread pool, one operation at a time per repository,
+/// so two commands never race on the same index lock.
+#[derive(Clone, Default)]
+pub struct RepoExecutor {
+    locks: Arc<Mutex<HashMap<String, Arc<async_runtime::Mutex<()>>>>>,
+}
+
+impl RepoExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for any other operation on `path` to finish, then runs `work` with the path.
+    pub async fn run<T, F>(&self, path: String, work: F) -> Result<T, GitError>
+    where
+        F: FnOnce(&str) -> Result<T, GitError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let lock = self.lock_for(&path);
+        let _guard = lock.lock_owned().await;
+
+        blocking(move || work(&path)).await
+    }
+
+    /// Whether an operation is running or waiting on the repository containing `path`.
+    pub fn is_busy(&self, path: &str) -> bool {
+        self.lock_for(path).try_lock().is_err()
+    }
+
+    fn lock_for(&self, path: &str) -> Arc<async_runtime::Mutex<()>> {
+        // Commands accept any folder inside the working tree, so the lock belongs to the git
+        // directory they resolve to. Paths outside a repository lock on their own.
+        let dir =
+            Repository::discover_path(path, &[] as &[&str]).unwrap_or_else(|_| PathBuf::from(path));
+        let key = std::fs::canonicalize(&dir)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string());
+
+        let mut locks = match self.locks.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        locks.entry(key).or_default().clone()
+    }
+}
+
+/// Runs work that is not tied to a repository on the blocking thread pool.
+pub async fn blocking<T, F>(work: F) -> Result<T, GitError>
+where
+    F: FnOnce() -> Result<T, GitError> + Send + 'static,
+    T: Send + 'static,
+{
+    async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| GitError::other(e.to_string()))?
+}
@@ -1,4 +1,13 @@
-use git2::{BranchType, Repository, Status, StatusOptions};
+use crate::credentials::{CredentialHandler, CredentialResponse};
+use crate::discovery::open_repository;
+use crate::error::{ErrorKind, GitError};
+use crate::progress::{checkout_builder, remote_callbacks, ProgressReporter};
+use crate::remotes::tracking_ref;
+use crate::submodules::{submodule_change, update_repo_submodules, SubmoduleChange};
+use crate::templates::{gitignore_for, license_for};
+use git2::{
+    AutotagOption, BranchType, FetchOptions, FetchPrune, Oid, Repository, Status, StatusOptions,
+};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
@@ -12,6 +21,7 @@ pub struct GitVersionInfo {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RepoInfo {
     pub path: String,
+    pub root: String, // top of the working tree, `path` may be a folder inside it
     pub name: String,
     pub current_branch: String,
     pub has_changes: bool,
@@ -25,6 +35,7 @@ pub struct FileStatus {
     pub path: String,
     pub status: String,
     pub staged: bool,
+    pub submodule: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -60,6 +71,84 @@ pub struct FileDiff {
     pub new_content: String,
     pub patch: String,
     pub lines: Vec<DiffLine>,
+    pub submodule: Option<SubmoduleChange>, // set instead of a text diff for submodules
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PullResult {
+    pub status: String, // "up_to_date", "fast_forward", "merged", "rebased", "conflicts", "diverged"
+    pub strategy: String, // "merge", "rebase", "ff-only"
+    pub remote: String,
+    pub upstream: String,
+    pub old_head: Option<String>,
+    pub new_head: Option<String>,
+    pub conflicted_files: Vec<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefUpdate {
+    pub name: String,
+    pub remote: String,
+    pub kind: String, // "new", "updated", "deleted"
+    pub old_id: Option<String>,
+    pub new_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FetchResult {
+    pub remotes: Vec<String>,
+    pub updates: Vec<RefUpdate>,
+    pub repo_info: Option<RepoInfo>, // None for bare repositories, they have no working tree
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PushOptions {
+    pub remote: Option<String>,
+    pub branch: Option<String>, // local branch, the current one by default
+    pub destination: Option<String>, // branch on the remote, the same name by default
+    pub force_with_lease: bool,
+    pub set_upstream: Option<bool>, // by default only set when the branch has no upstream
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PushRefStatus {
+    pub local_ref: String,
+    pub remote_ref: String,
+    pub status: String, // "ok", "non_fast_forward", "stale", "hook_declined", "rejected"
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    pub destination: String,
+    pub forced: bool,
+    pub updates: Vec<PushRefStatus>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CloneOptions {
+    pub branch: Option<String>,
+    pub depth: Option<i32>, // shallow clone with this many commits
+    pub bare: bool,
+    pub mirror: bool, // implies bare
+    pub recurse_submodules: bool,
+    pub remote_name: Option<String>, // "origin" by default
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InitOptions {
+    pub initial_branch: Option<String>, // init.defaultBranch, then "main", by default
+    pub bare: bool,
+    pub gitignore: Vec<String>, // names of bundled .gitignore templates
+    pub readme: bool,
+    pub license: Option<String>, // SPDX identifier of a bundled license
+    pub initial_commit: bool,
+    pub remote_url: Option<String>,
+    pub remote_name: Option<String>, // "origin" by default
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -69,14 +158,14 @@ pub struct BranchInfo {
     pub is_remote: bool,
 }
 
-pub fn get_git_version() -> Result<GitVersionInfo, String> {
+pub fn get_git_version() -> Result<GitVersionInfo, GitError> {
     let output = Command::new("git")
         .arg("--version")
         .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+        .map_err(|e| GitError::other(format!("Failed to execute git command: {}", e)))?;
 
     if !output.status.success() {
-        return Err("Git command failed".to_string());
+        return Err("Git command failed".into());
     }
 
     let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -100,31 +189,39 @@ pub fn get_git_version() -> Result<GitVersionInfo, String> {
     Ok(GitVersionInfo { version, path })
 }
 
-pub fn get_repo_info(path: &str) -> Result<RepoInfo, String> {
+pub fn get_repo_info(repo: &Repository, path: &str) -> Result<RepoInfo, GitError> {
     println!("[Rust] Getting repo info for: {}", path);
-    let repo = match Repository::open(path) {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("[Rust] Failed to open repository at {}: {}", path, e);
-            return Err(e.to_string());
-        }
+
+    let Some(workdir) = repo.workdir() else {
+        return Err(GitError::new(
+            ErrorKind::BareRepository,
+            format!(
+                "{} is a bare repository, it has no working tree to show",
+                repo.path().to_string_lossy().trim_end_matches(['/', '\\'])
+            ),
+        ));
     };
+    let root = workdir
+        .to_string_lossy()
+        .trim_end_matches(['/', '\\'])
+        .to_string();
 
     // Handle both normal branches and unborn branches (newly initialized repos)
     let current_branch = match repo.head() {
         Ok(head) => head.shorthand().unwrap_or("HEAD").to_string(),
         Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
             // Repository is newly initialized with no commits
-            println!("[Rust] Unborn branch detected, using 'main'");
-            "main".to_string()
+            let branch = unborn_branch_name(repo);
+            println!("[Rust] Unborn branch detected, using '{}'", branch);
+            branch
         }
         Err(e) => {
             eprintln!("[Rust] Failed to get HEAD: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
     };
 
-    let name = Path::new(path)
+    let name = Path::new(&root)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("Unknown")
@@ -132,11 +229,11 @@ pub fn get_repo_info(path: &str) -> Result<RepoInfo, String> {
 
     let mut opts = StatusOptions::new();
     opts.include_untracked(true);
-    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    let statuses = repo.statuses(Some(&mut opts))?;
     let has_changes = !statuses.is_empty();
 
-    let (ahead, behind) = get_ahead_behind(&repo, &current_branch);
-    let has_remote = repo.remotes().map(|r| r.len() > 0).unwrap_or(false);
+    let (ahead, behind) = get_ahead_behind(repo, &current_branch);
+    let has_remote = repo.remotes().map(|r| !r.is_empty()).unwrap_or(false);
 
     println!(
         "[Rust] Successfully got repo info for: {} (branch: {})",
@@ -144,6 +241,7 @@ pub fn get_repo_info(path: &str) -> Result<RepoInfo, String> {
     );
     Ok(RepoInfo {
         path: path.to_string(),
+        root,
         name,
         current_branch,
         has_changes,
@@ -153,14 +251,12 @@ pub fn get_repo_info(path: &str) -> Result<RepoInfo, String> {
     })
 }
 
-pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
-    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
-
+pub fn get_repo_status(repo: &Repository, repo_path: &str) -> Result<RepoStatus, GitError> {
     // Handle both normal branches and unborn branches
     let current_branch = match repo.head() {
         Ok(head) => head.shorthand().unwrap_or("HEAD").to_string(),
-        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => "main".to_string(),
-        Err(e) => return Err(e.to_string()),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => unborn_branch_name(repo),
+        Err(e) => return Err(e.into()),
     };
 
     let mut opts = StatusOptions::new();
@@ -168,7 +264,16 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
     opts.include_ignored(false);
     opts.recurse_untracked_dirs(true);
 
-    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    let statuses = repo.statuses(Some(&mut opts))?;
+    let submodule_paths: Vec<String> = repo
+        .submodules()
+        .map(|submodules| {
+            submodules
+                .iter()
+                .map(|s| s.path().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
 
     println!(
         "[Rust] Found {} status entries for {}",
@@ -176,6 +281,8 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
         repo_path
     );
 
+    // Status paths are relative to the working tree, `repo_path` may be a folder inside it
+    let workdir = repo.workdir().unwrap_or_else(|| Path::new(repo_path));
     let mut files = Vec::new();
     for entry in statuses.iter() {
         let status = entry.status();
@@ -186,10 +293,11 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
             file_path, status
         );
 
-        // Skip directories (only include actual files)
+        // Skip directories (only include actual files and submodules)
         // Check if path exists first to avoid issues with deleted files
-        let full_path = Path::new(repo_path).join(&file_path);
-        if full_path.exists() && full_path.is_dir() {
+        let submodule = submodule_paths.contains(&file_path);
+        let full_path = workdir.join(&file_path);
+        if !submodule && full_path.exists() && full_path.is_dir() {
             println!("[Rust] Skipping directory: {}", file_path);
             continue;
         }
@@ -222,6 +330,7 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
             path: file_path.clone(),
             status: status_str.to_string(),
             staged,
+            submodule,
         });
 
         println!(
@@ -230,9 +339,9 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
         );
     }
 
-    let (ahead, behind) = get_ahead_behind(&repo, &current_branch);
+    let (ahead, behind) = get_ahead_behind(repo, &current_branch);
     let has_changes = !files.is_empty() || ahead > 0 || behind > 0;
-    let has_remote = repo.remotes().map(|r| r.len() > 0).unwrap_or(false);
+    let has_remote = repo.remotes().map(|r| !r.is_empty()).unwrap_or(false);
 
     println!("[Rust] Returning {} files for {}", files.len(), repo_path);
 
@@ -257,8 +366,15 @@ pub fn get_repo_status(repo_path: &str) -> Result<RepoStatus, String> {
     Ok(result)
 }
 
-pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<FileDiff, String> {
-    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+pub fn get_file_diff(
+    repo: &Repository,
+    repo_path: &str,
+    file_path: &str,
+    staged: bool,
+) -> Result<FileDiff, GitError> {
+    if let Some(change) = submodule_change(repo, file_path, staged)? {
+        return Ok(submodule_diff(change));
+    }
 
     let mut diff_options = git2::DiffOptions::new();
     diff_options.pathspec(file_path);
@@ -271,8 +387,7 @@ pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<F
     } else {
         // Diff between index and working directory (unstaged changes)
         repo.diff_index_to_workdir(None, Some(&mut diff_options))
-    }
-    .map_err(|e| e.to_string())?;
+    }?;
 
     let mut patch = String::new();
     let mut diff_lines: Vec<DiffLine> = Vec::new();
@@ -341,11 +456,13 @@ pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<F
             }
         }
         true
-    })
-    .map_err(|e| e.to_string())?;
+    })?;
 
     // Get old and new content
-    let file_full_path = Path::new(repo_path).join(file_path);
+    let file_full_path = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(repo_path))
+        .join(file_path);
     let new_content = if file_full_path.exists() {
         std::fs::read_to_string(&file_full_path).unwrap_or_default()
     } else {
@@ -378,83 +495,138 @@ pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<F
         new_content,
         patch,
         lines: diff_lines,
+        submodule: None,
     })
 }
 
-pub fn stage_file(repo_path: &str, file_path: &str) -> Result<(), String> {
-    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
-    let mut index = repo.index().map_err(|e| e.to_string())?;
+/// A submodule pointer change written the way `git diff` shows it, with the commit
+/// range alongside for the frontend to list.
+fn submodule_diff(change: SubmoduleChange) -> FileDiff {
+    let line = |id: &Option<String>, dirty: bool| {
+        id.as_ref().map(|id| {
+            format!(
+                "Subproject commit {}{}",
+                id,
+                if dirty { "-dirty" } else { "" }
+            )
+        })
+    };
+    let old_content = line(&change.old_id, false).unwrap_or_default();
+    let new_content = line(&change.new_id, change.dirty).unwrap_or_default();
 
-    index
-        .add_path(Path::new(file_path))
-        .map_err(|e| e.to_string())?;
-    index.write().map_err(|e| e.to_string())?;
+    let mut patch = String::new();
+    let mut lines = Vec::new();
+    if !old_content.is_empty() {
+        patch.push_str(&format!("-{}\n", old_content));
+        lines.push(DiffLine {
+            old_line_num: Some(1),
+            new_line_num: None,
+            content: old_content.clone(),
+            line_type: "delete".to_string(),
+        });
+    }
+    if !new_content.is_empty() {
+        patch.push_str(&format!("+{}\n", new_content));
+        lines.push(DiffLine {
+            old_line_num: None,
+            new_line_num: Some(1),
+            content: new_content.clone(),
+            line_type: "add".to_string(),
+        });
+    }
+
+    FileDiff {
+        old_content,
+        new_content,
+        patch,
+        lines,
+        submodule: Some(change),
+    }
+}
+
+pub fn stage_file(repo_path: &str, file_path: &str) -> Result<(), GitError> {
+    let repo = open_repository(repo_path)?;
+    let mut index = repo.index()?;
+
+    index.add_path(Path::new(file_path))?;
+    index.write()?;
 
     Ok(())
 }
 
-pub fn unstage_file(path: &str, file_path: &str) -> Result<(), String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
+pub fn unstage_file(path: &str, file_path: &str) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
 
     // Check if we have any commits (HEAD exists)
     match repo.head() {
         Ok(head) => {
             // Repository has commits, reset to HEAD
-            let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
-            repo.reset_default(Some(&head_commit.as_object()), &[Path::new(file_path)])
-                .map_err(|e| e.to_string())?;
+            let head_commit = head.peel_to_commit()?;
+            repo.reset_default(Some(&head_commit.as_object()), &[Path::new(file_path)])?;
         }
         Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
             // No commits yet, just remove from index
-            let mut index = repo.index().map_err(|e| e.to_string())?;
-            index
-                .remove_path(Path::new(file_path))
-                .map_err(|e| e.to_string())?;
-            index.write().map_err(|e| e.to_string())?;
+            let mut index = repo.index()?;
+            index.remove_path(Path::new(file_path))?;
+            index.write()?;
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     }
 
     Ok(())
 }
 
-pub fn commit(path: &str, message: &str) -> Result<String, String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-    let mut index = repo.index().map_err(|e| e.to_string())?;
-    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
-    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+pub fn commit(path: &str, message: &str) -> Result<String, GitError> {
+    let repo = open_repository(path)?;
+    let mut index = repo.index()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
 
-    let signature = repo.signature().map_err(|e| e.to_string())?;
+    let signature = repo.signature()?;
 
     // Check if this is the first commit (no HEAD yet)
     let oid = match repo.head() {
         Ok(head) => {
             // Repository has commits, create commit with parent
-            let parent_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
-            repo.commit(
+            let parent_commit = head.peel_to_commit()?;
+            let mut parents = vec![parent_commit];
+
+            // Concluding a merge that stopped on conflicts
+            let concluding_merge = repo.state() == git2::RepositoryState::Merge;
+            if concluding_merge {
+                let merge_heads = std::fs::read_to_string(repo.path().join("MERGE_HEAD"))?;
+                for line in merge_heads.lines().filter(|l| !l.trim().is_empty()) {
+                    let oid = Oid::from_str(line.trim())?;
+                    parents.push(repo.find_commit(oid)?);
+                }
+            }
+
+            let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+            let oid = repo.commit(
                 Some("HEAD"),
                 &signature,
                 &signature,
                 message,
                 &tree,
-                &[&parent_commit],
-            )
-            .map_err(|e| e.to_string())?
+                &parent_refs,
+            )?;
+            if concluding_merge {
+                repo.cleanup_state()?;
+            }
+            oid
         }
         Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
             // First commit, no parent
-            repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[])
-                .map_err(|e| e.to_string())?
+            repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[])?
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     };
 
     Ok(oid.to_string())
 }
 
-pub fn get_commits(path: &str, limit: usize) -> Result<Vec<CommitInfo>, String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+pub fn get_commits(repo: &Repository, limit: usize) -> Result<Vec<CommitInfo>, GitError> {
+    let mut revwalk = repo.revwalk()?;
 
     // Try to push HEAD, but handle unborn branch (no commits yet)
     match revwalk.push_head() {
@@ -463,7 +635,7 @@ pub fn get_commits(path: &str, limit: usize) -> Result<Vec<CommitInfo>, String>
             // No commits yet in this repository
             return Ok(Vec::new());
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     }
 
     let mut commits = Vec::new();
@@ -472,8 +644,8 @@ pub fn get_commits(path: &str, limit: usize) -> Result<Vec<CommitInfo>, String>
             break;
         }
 
-        let oid = oid.map_err(|e| e.to_string())?;
-        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
 
         commits.push(CommitInfo {
             id: commit.id().to_string(),
@@ -487,136 +659,661 @@ pub fn get_commits(path: &str, limit: usize) -> Result<Vec<CommitInfo>, String>
     Ok(commits)
 }
 
-pub fn pull(path: &str) -> Result<String, String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-
-    // Fetch
-    let mut remote = repo.find_remote("origin").map_err(|e| e.to_string())?;
-    remote
-        .fetch(&["HEAD"], None, None)
-        .map_err(|e| e.to_string())?;
-
-    // Get the fetch head
-    let fetch_head = repo
-        .find_reference("FETCH_HEAD")
-        .map_err(|e| e.to_string())?;
-    let fetch_commit = repo
-        .reference_to_annotated_commit(&fetch_head)
-        .map_err(|e| e.to_string())?;
-
-    // Merge
-    let analysis = repo
-        .merge_analysis(&[&fetch_commit])
-        .map_err(|e| e.to_string())?;
-
-    if analysis.0.is_fast_forward() {
-        let refname = format!(
-            "refs/heads/{}",
-            repo.head().unwrap().shorthand().unwrap_or("master")
-        );
-        let mut reference = repo.find_reference(&refname).map_err(|e| e.to_string())?;
-        reference
-            .set_target(fetch_commit.id(), "Fast-Forward")
-            .map_err(|e| e.to_string())?;
-        repo.set_head(&refname).map_err(|e| e.to_string())?;
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-            .map_err(|e| e.to_string())?;
-        Ok("Fast-forward successful".to_string())
-    } else if analysis.0.is_up_to_date() {
-        Ok("Already up to date".to_string())
-    } else {
-        Ok("Merge required (not implemented in MVP)".to_string())
+const PULL_STRATEGIES: [&str; 3] = ["merge", "rebase", "ff-only"];
+
+pub fn pull(
+    path: &str,
+    strategy: Option<&str>,
+    progress: Option<&ProgressReporter>,
+) -> Result<PullResult, GitError> {
+    if let Some(strategy) = strategy.filter(|s| !PULL_STRATEGIES.contains(s)) {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unknown pull strategy {}, expected one of {}",
+                strategy,
+                PULL_STRATEGIES.join(", ")
+            ),
+        ));
+    }
+    let repo = open_repository(path)?;
+
+    // HEAD may point at a branch that has no commits yet
+    let head_ref = repo.find_reference("HEAD")?;
+    let branch_ref = match head_ref.symbolic_target() {
+        Some(target) => target.to_string(),
+        None => {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                "Cannot pull: HEAD is detached",
+            ))
+        }
+    };
+    let branch_name = branch_ref.trim_start_matches("refs/heads/").to_string();
+    let old_head = repo.refname_to_id(&branch_ref).ok();
+
+    let upstream = resolve_upstream(&repo, &branch_ref, &branch_name)?;
+    let strategy = match strategy {
+        Some(s) => s.to_string(),
+        None => configured_pull_strategy(&repo, &branch_name),
+    };
+
+    println!(
+        "[Rust Pull] Pulling {} from {} ({})",
+        branch_name, upstream.tracking_ref, strategy
+    );
+
+    fetch_remote(&repo, &upstream.remote, None, None, progress)?;
+
+    let upstream_ref = repo.find_reference(&upstream.tracking_ref).map_err(|_| {
+        format!(
+            "Upstream branch '{}' does not exist on remote '{}'",
+            upstream.merge_ref.trim_start_matches("refs/heads/"),
+            upstream.remote
+        )
+    })?;
+    let upstream_commit = repo.reference_to_annotated_commit(&upstream_ref)?;
+    let upstream_name = upstream_ref.shorthand().unwrap_or("").to_string();
+
+    let mut result = PullResult {
+        status: "up_to_date".to_string(),
+        strategy: strategy.clone(),
+        remote: upstream.remote.clone(),
+        upstream: upstream_name.clone(),
+        old_head: old_head.map(|oid| oid.to_string()),
+        new_head: old_head.map(|oid| oid.to_string()),
+        conflicted_files: Vec::new(),
+        message: "Already up to date".to_string(),
+    };
+
+    // Nothing checked out yet, simply start the branch at the upstream commit
+    if old_head.is_none() {
+        let target = repo.find_object(upstream_commit.id(), None)?;
+        repo.checkout_tree(&target, Some(&mut checkout_builder(progress)))?;
+        repo.reference(&branch_ref, upstream_commit.id(), true, "pull: initial")?;
+        repo.set_head(&branch_ref)?;
+
+        result.status = "fast_forward".to_string();
+        result.new_head = Some(upstream_commit.id().to_string());
+        result.message = format!("Checked out {} from {}", branch_name, upstream_name);
+        return Ok(result);
+    }
+
+    let (analysis, preference) = repo.merge_analysis(&[&upstream_commit])?;
+    let no_ff = strategy == "merge" && preference.is_no_fast_forward();
+
+    if analysis.is_up_to_date() {
+        return Ok(result);
+    }
+
+    if analysis.is_fast_forward() && !no_ff {
+        // Check out first so local changes that would be overwritten abort the pull
+        let target = repo.find_object(upstream_commit.id(), None)?;
+        repo.checkout_tree(&target, Some(&mut checkout_builder(progress)))?;
+        let mut reference = repo.find_reference(&branch_ref)?;
+        reference.set_target(upstream_commit.id(), "pull: Fast-forward")?;
+
+        result.status = "fast_forward".to_string();
+        result.new_head = Some(upstream_commit.id().to_string());
+        result.message = format!("Fast-forwarded {} to {}", branch_name, upstream_name);
+        return Ok(result);
+    }
+
+    match strategy.as_str() {
+        "ff-only" => {
+            result.status = "diverged".to_string();
+            result.message = format!(
+                "{} and {} have diverged; cannot fast-forward",
+                branch_name, upstream_name
+            );
+            Ok(result)
+        }
+        "rebase" => {
+            let rebase_status = crate::rebase::rebase_onto(path, &upstream_name)?;
+            result.conflicted_files = rebase_status.conflicted_files;
+            if rebase_status.state == "completed" {
+                result.status = "rebased".to_string();
+                result.new_head = repo
+                    .refname_to_id(&branch_ref)
+                    .ok()
+                    .map(|oid| oid.to_string());
+                result.message = format!("Rebased {} onto {}", branch_name, upstream_name);
+            } else {
+                result.status = "conflicts".to_string();
+                result.message = format!(
+                    "Rebase stopped at step {}/{} with conflicts",
+                    rebase_status.current_step, rebase_status.total_steps
+                );
+            }
+            Ok(result)
+        }
+        _ => {
+            repo.merge(&[&upstream_commit], None, None)?;
+
+            let mut index = repo.index()?;
+            if index.has_conflicts() {
+                result.status = "conflicts".to_string();
+                result.conflicted_files = crate::rebase::conflicted_paths(&repo)?;
+                result.message = format!(
+                    "Merge of {} has conflicts; resolve them and commit",
+                    upstream_name
+                );
+                return Ok(result);
+            }
+
+            let tree_id = index.write_tree()?;
+            let tree = repo.find_tree(tree_id)?;
+            let signature = repo.signature()?;
+            let local = repo.find_commit(old_head.unwrap_or_else(Oid::zero))?;
+            let theirs = repo.find_commit(upstream_commit.id())?;
+            let message = format!(
+                "Merge remote-tracking branch '{}' into {}",
+                upstream_name, branch_name
+            );
+
+            let oid = repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &[&local, &theirs],
+            )?;
+            repo.cleanup_state()?;
+
+            result.status = "merged".to_string();
+            result.new_head = Some(oid.to_string());
+            result.message = message;
+            Ok(result)
+        }
     }
 }
 
-pub fn push(path: &str) -> Result<String, String> {
-    push_with_credentials(path, None, None)
+struct Upstream {
+    remote: String,
+    merge_ref: String,
+    tracking_ref: String,
 }
 
+fn resolve_upstream(
+    repo: &Repository,
+    branch_ref: &str,
+    branch_name: &str,
+) -> Result<Upstream, GitError> {
+    let config = repo.config()?;
+
+    if let Ok(remote) = repo.branch_upstream_remote(branch_ref) {
+        let remote = remote.as_str().unwrap_or("origin").to_string();
+        let merge_ref = config.get_string(&format!("branch.{}.merge", branch_name))?;
+        let tracking_ref = match repo.branch_upstream_name(branch_ref) {
+            Ok(name) => name.as_str().unwrap_or("").to_string(),
+            Err(_) => format!(
+                "refs/remotes/{}/{}",
+                remote,
+                merge_ref.trim_start_matches("refs/heads/")
+            ),
+        };
+        return Ok(Upstream {
+            remote,
+            merge_ref,
+            tracking_ref,
+        });
+    }
+
+    // No upstream configured yet, assume the same branch name on origin
+    if repo.find_remote("origin").is_err() {
+        return Err(GitError::new(
+            ErrorKind::NotFound,
+            format!(
+                "Branch '{}' has no upstream and there is no 'origin' remote",
+                branch_name
+            ),
+        ));
+    }
+    Ok(Upstream {
+        remote: "origin".to_string(),
+        merge_ref: format!("refs/heads/{}", branch_name),
+        tracking_ref: format!("refs/remotes/origin/{}", branch_name),
+    })
+}
+
+/// Reads `branch.<name>.rebase`, `pull.rebase` and `pull.ff` the way `git pull` does.
+/// `merges` and `interactive` also give a plain rebase: merge commits are not recreated
+/// and no todo list is shown, the interactive rebase has its own command.
+fn configured_pull_strategy(repo: &Repository, branch_name: &str) -> String {
+    let config = match repo.config() {
+        Ok(config) => config,
+        Err(_) => return "merge".to_string(),
+    };
+
+    // The branch setting wins over pull.rebase, even when it turns rebasing off. Besides
+    // booleans (true/yes/on/1, false/no/off/0) git accepts merges and interactive.
+    let keys = [
+        format!("branch.{}.rebase", branch_name),
+        "pull.rebase".to_string(),
+    ];
+    let rebase = keys.iter().find_map(|key| {
+        config.get_bool(key).ok().or_else(|| {
+            let value = config.get_string(key).ok()?;
+            Some(matches!(
+                value.to_ascii_lowercase().as_str(),
+                "merges" | "interactive" | "m" | "i"
+            ))
+        })
+    });
+    if rebase == Some(true) {
+        return "rebase".to_string();
+    }
+
+    match config.get_string("pull.ff").unwrap_or_default().as_str() {
+        "only" => "ff-only".to_string(),
+        _ => "merge".to_string(),
+    }
+}
+
+pub fn fetch(
+    path: &str,
+    remote_name: Option<&str>,
+    prune: Option<bool>,
+    tags: Option<&str>,
+    progress: Option<&ProgressReporter>,
+) -> Result<FetchResult, GitError> {
+    let repo = open_repository(path)?;
+
+    let remotes: Vec<String> = match remote_name {
+        Some(name) => vec![name.to_string()],
+        None => repo
+            .remotes()?
+            .iter()
+            .flatten()
+            .map(|name| name.to_string())
+            .collect(),
+    };
+
+    let mut updates = Vec::new();
+    for name in &remotes {
+        println!("[Rust Fetch] Fetching {} in {}", name, path);
+        updates.extend(fetch_remote(&repo, name, prune, tags, progress)?);
+    }
+
+    println!(
+        "[Rust Fetch] Fetched {} remote(s), {} ref update(s)",
+        remotes.len(),
+        updates.len()
+    );
+
+    Ok(FetchResult {
+        remotes,
+        updates,
+        repo_info: if repo.is_bare() {
+            None
+        } else {
+            Some(get_repo_info(&repo, path)?)
+        },
+    })
+}
+
+/// Fetches a single remote with its configured refspecs and reports the refs that moved.
+fn fetch_remote(
+    repo: &Repository,
+    remote_name: &str,
+    prune: Option<bool>,
+    tags: Option<&str>,
+    progress: Option<&ProgressReporter>,
+) -> Result<Vec<RefUpdate>, GitError> {
+    let download_tags = match tags {
+        Some("all") => AutotagOption::All,
+        Some("none") => AutotagOption::None,
+        Some("auto") => AutotagOption::Auto,
+        // Fall back to remote.<name>.tagOpt
+        None => AutotagOption::Unspecified,
+        Some(other) => {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown tag mode {}, expected one of all, none, auto",
+                    other
+                ),
+            ))
+        }
+    };
+    let mut remote = repo.find_remote(remote_name)?;
+    let url = remote.url().map(str::to_string);
+    let mut updates = Vec::new();
+
+    {
+        let mut callbacks = remote_callbacks(progress, url.as_deref());
+        callbacks.update_tips(|refname, old, new| {
+            let kind = if old.is_zero() {
+                "new"
+            } else if new.is_zero() {
+                "deleted"
+            } else {
+                "updated"
+            };
+            updates.push(RefUpdate {
+                name: refname.to_string(),
+                remote: remote_name.to_string(),
+                kind: kind.to_string(),
+                old_id: (!old.is_zero()).then(|| old.to_string()),
+                new_id: (!new.is_zero()).then(|| new.to_string()),
+            });
+            true
+        });
+
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        fetch_options.prune(match prune {
+            Some(true) => FetchPrune::On,
+            Some(false) => FetchPrune::Off,
+            // Fall back to remote.<name>.prune / fetch.prune
+            None => FetchPrune::Unspecified,
+        });
+        fetch_options.download_tags(download_tags);
+
+        remote.fetch(&[] as &[&str], Some(&mut fetch_options), None)?;
+    }
+
+    Ok(updates)
+}
+
+pub fn push(path: &str, progress: Option<&ProgressReporter>) -> Result<String, GitError> {
+    push_with_credentials(path, None, None, progress)
+}
+
+/// Pushes the current branch with the default options, failing if the remote rejects it.
 pub fn push_with_credentials(
     path: &str,
     username: Option<String>,
     password: Option<String>,
-) -> Result<String, String> {
-    use git2::{Cred, PushOptions, RemoteCallbacks};
-
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-    let mut remote = repo.find_remote("origin").map_err(|e| e.to_string())?;
+    progress: Option<&ProgressReporter>,
+) -> Result<String, GitError> {
+    let result = push_branch(path, &PushOptions::default(), username, password, progress)?;
+    match result.updates.iter().find(|update| update.status != "ok") {
+        Some(rejected) => {
+            let kind = match rejected.status.as_str() {
+                "non_fast_forward" | "stale" => ErrorKind::NonFastForward,
+                _ => ErrorKind::Other,
+            };
+            Err(GitError::new(kind, result.message))
+        }
+        None => Ok(result.message),
+    }
+}
 
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let branch_name = head.shorthand().unwrap_or("master");
-    let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
+/// Pushes a local branch to a branch of a remote. The remote defaults to
+/// `branch.<name>.pushRemote`, `remote.pushDefault`, the upstream remote, then `origin`,
+/// and the destination to the branch of the same name.
+/// Refs the remote refuses are reported in the result rather than as an error.
+pub fn push_branch(
+    path: &str,
+    options: &PushOptions,
+    username: Option<String>,
+    password: Option<String>,
+    progress: Option<&ProgressReporter>,
+) -> Result<PushResult, GitError> {
+    use git2::{ErrorClass, ErrorCode};
+    use std::cell::{Cell, RefCell};
+
+    let repo = open_repository(path)?;
+    let branch_name = match &options.branch {
+        Some(branch) => branch.clone(),
+        None => {
+            let head = match repo.head() {
+                Ok(head) => head,
+                Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                    return Err(GitError::new(
+                        ErrorKind::UnbornBranch,
+                        format!(
+                            "{} has no commits yet, commit something before pushing",
+                            unborn_branch_name(&repo)
+                        ),
+                    ));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if !head.is_branch() {
+                return Err(GitError::new(
+                    ErrorKind::InvalidInput,
+                    "HEAD is detached, choose the branch to push",
+                ));
+            }
+            head.shorthand().unwrap_or("HEAD").to_string()
+        }
+    };
+    repo.find_branch(&branch_name, BranchType::Local)?;
 
-    // Setup callbacks for authentication
-    let mut callbacks = RemoteCallbacks::new();
+    let remote_name = match &options.remote {
+        Some(remote) => remote.clone(),
+        None => default_push_remote(&repo, &branch_name)?,
+    };
+    let destination = options
+        .destination
+        .clone()
+        .unwrap_or_else(|| branch_name.clone());
+    let local_ref = format!("refs/heads/{}", branch_name);
+    let remote_ref = format!("refs/heads/{}", destination);
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    // With a lease the remote branch must still be where it was when last fetched,
+    // or missing if it was never fetched
+    let lease = if options.force_with_lease {
+        let expected = tracking_ref(&remote, &remote_ref)
+            .and_then(|tracking| repo.refname_to_id(&tracking).ok())
+            .unwrap_or_else(Oid::zero);
+        Some(expected)
+    } else {
+        None
+    };
+    let refspec = format!(
+        "{}{}:{}",
+        if lease.is_some() { "+" } else { "" },
+        local_ref,
+        remote_ref
+    );
 
-    let username_clone = username.clone();
-    let password_clone = password.clone();
+    println!(
+        "[Rust Push] Pushing {} to {} ({}){}",
+        branch_name,
+        remote_name,
+        remote_ref,
+        if lease.is_some() { " with lease" } else { "" }
+    );
 
-    callbacks.credentials(move |_url, username_from_url, _allowed_types| {
-        if let (Some(user), Some(pass)) = (&username_clone, &password_clone) {
-            // Use provided credentials (HTTPS with PAT)
-            Cred::userpass_plaintext(user, pass)
-        } else if let Some(user) = username_from_url {
-            // Try SSH agent
-            Cred::ssh_key_from_agent(user)
-        } else {
-            // Default SSH agent
-            Cred::ssh_key_from_agent("git")
-        }
+    // Credentials given up-front are tried first, then the SSH agent, then the user is asked
+    let initial = password.clone().map(|password| CredentialResponse {
+        username: username.clone(),
+        password: Some(password),
     });
+    let mut credentials = CredentialHandler::new(progress, initial);
+    let stale = Cell::new(false);
+    let updates: RefCell<Vec<PushRefStatus>> = RefCell::new(Vec::new());
+
+    let push_url = remote.pushurl().or(remote.url()).map(str::to_string);
+    let pushed = {
+        let mut callbacks = remote_callbacks(progress, push_url.as_deref());
+        callbacks.credentials(|url, username_from_url, allowed_types| {
+            credentials.credentials(url, username_from_url, allowed_types)
+        });
+        callbacks.push_negotiation(|negotiated| {
+            if progress.is_some_and(|p| p.is_cancelled()) {
+                return Err(git2::Error::from_str("Operation cancelled"));
+            }
+            let Some(expected) = lease else {
+                return Ok(());
+            };
+            for update in negotiated {
+                if update.dst_refname() == Some(remote_ref.as_str()) && update.src() != expected {
+                    stale.set(true);
+                    return Err(git2::Error::new(
+                        ErrorCode::NotFastForward,
+                        ErrorClass::Reference,
+                        "stale info",
+                    ));
+                }
+            }
+            Ok(())
+        });
+        callbacks.push_update_reference(|refname, status| {
+            updates.borrow_mut().push(PushRefStatus {
+                local_ref: local_ref.clone(),
+                remote_ref: refname.to_string(),
+                status: rejection_kind(status).to_string(),
+                message: status.map(str::to_string),
+            });
+            Ok(())
+        });
 
-    let mut push_options = PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+        let mut push_options = git2::PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        remote.push(&[&refspec], Some(&mut push_options))
+    };
 
-    match remote.push(&[&refspec], Some(&mut push_options)) {
-        Ok(_) => {
-            // Set upstream tracking
-            if let Ok(mut branch) = repo.find_branch(&branch_name, BranchType::Local) {
-                let _ = branch.set_upstream(Some(&format!("origin/{}", branch_name)));
-            }
-            Ok("Push successful".to_string())
+    let mut updates = updates.into_inner();
+    match pushed {
+        _ if stale.get() => {
+            updates = vec![PushRefStatus {
+                local_ref: local_ref.clone(),
+                remote_ref: remote_ref.clone(),
+                status: "stale".to_string(),
+                message: Some("the remote branch moved since it was last fetched".to_string()),
+            }];
+        }
+        Ok(()) => {}
+        // libgit2 refuses by itself when the remote has commits missing locally
+        Err(e) if e.code() == ErrorCode::NotFastForward => {
+            updates = vec![PushRefStatus {
+                local_ref: local_ref.clone(),
+                remote_ref: remote_ref.clone(),
+                status: "non_fast_forward".to_string(),
+                message: Some(e.message().to_string()),
+            }];
         }
         Err(e) => {
-            let error_msg = e.to_string();
-            if error_msg.contains("401")
-                || error_msg.contains("403")
-                || error_msg.contains("authentication")
-            {
-                Err("Authentication failed. Please check your GitHub username and Personal Access Token have the correct permissions (repo scope required).".to_string())
+            // The remote asked again after credentials were sent, or they ran out. Other
+            // HTTP failures, like a 404 or a proxy error, keep their own kind.
+            let rejected = e.code() == ErrorCode::Auth || credentials.rejected();
+            let error = GitError::from(e);
+            if !rejected {
+                return Err(error);
+            }
+
+            return Err(error.with_kind(if password.is_some() {
+                ErrorKind::AuthFailed
             } else {
-                Err(error_msg)
+                ErrorKind::AuthRequired
+            }));
+        }
+    }
+
+    let target = format!("{}/{}", remote_name, destination);
+    let rejected: Vec<&PushRefStatus> = updates.iter().filter(|u| u.status != "ok").collect();
+    let message = match rejected.first() {
+        Some(update) => format!(
+            "Push of {} to {} was rejected: {}",
+            branch_name,
+            target,
+            update
+                .message
+                .as_deref()
+                .unwrap_or("rejected by the remote")
+        ),
+        None => {
+            let mut branch = repo.find_branch(&branch_name, BranchType::Local)?;
+            let set_upstream = options
+                .set_upstream
+                .unwrap_or_else(|| branch.upstream().is_err());
+            if set_upstream {
+                if let Err(e) = branch.set_upstream(Some(&target)) {
+                    println!("[Rust Push] Could not set upstream to {}: {}", target, e);
+                }
             }
+            format!("Pushed {} to {}", branch_name, target)
         }
+    };
+    println!("[Rust Push] {}", message);
+
+    Ok(PushResult {
+        remote: remote_name,
+        branch: branch_name,
+        destination,
+        forced: lease.is_some(),
+        updates,
+        message,
+    })
+}
+
+/// The remote a branch is pushed to when none is given, following git's lookup order.
+fn default_push_remote(repo: &Repository, branch_name: &str) -> Result<String, GitError> {
+    let config = repo.config()?;
+    let configured = [
+        format!("branch.{}.pushRemote", branch_name),
+        "remote.pushDefault".to_string(),
+        format!("branch.{}.remote", branch_name),
+    ]
+    .iter()
+    .find_map(|key| config.get_string(key).ok())
+    // "." means the repository itself, which is not something to push to
+    .filter(|name| name != ".");
+    if let Some(name) = configured {
+        return Ok(name);
+    }
+
+    let remotes = repo.remotes()?;
+    let names: Vec<&str> = remotes.iter().flatten().collect();
+    match names.as_slice() {
+        [] => Err(GitError::new(
+            ErrorKind::NotFound,
+            "No remote configured. Add a remote first",
+        )),
+        _ if names.contains(&"origin") => Ok("origin".to_string()),
+        [only] => Ok(only.to_string()),
+        _ => Err(GitError::new(
+            ErrorKind::InvalidInput,
+            "Several remotes are configured, choose the one to push to",
+        )),
+    }
+}
+
+/// Classifies the status message the remote sent for a ref, None meaning it was updated.
+fn rejection_kind(status: Option<&str>) -> &'static str {
+    match status {
+        None => "ok",
+        Some(message)
+            if message.contains("non-fast-forward") || message.contains("fetch first") =>
+        {
+            "non_fast_forward"
+        }
+        Some(message) if message.contains("stale info") => "stale",
+        Some(message) if message.contains("hook declined") => "hook_declined",
+        Some(_) => "rejected",
     }
 }
 
-pub fn get_branches(path: &str) -> Result<Vec<BranchInfo>, String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
+pub fn get_branches(repo: &Repository) -> Result<Vec<BranchInfo>, GitError> {
+    let mut branches = Vec::new();
 
     // Check if repo has any commits (handle unborn branch)
     let current_branch = match repo.head() {
         Ok(head) => head.shorthand().unwrap_or("").to_string(),
         Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
-            // No commits yet, return default main branch
-            return Ok(vec![BranchInfo {
-                name: "main".to_string(),
+            // No commits on it yet, so it is not among the branches listed below
+            let name = unborn_branch_name(repo);
+            branches.push(BranchInfo {
+                name: name.clone(),
                 is_current: true,
                 is_remote: false,
-            }]);
+            });
+            name
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     };
 
-    let mut branches = Vec::new();
-
     // Local branches
-    let local_branches = repo
-        .branches(Some(BranchType::Local))
-        .map_err(|e| e.to_string())?;
+    let local_branches = repo.branches(Some(BranchType::Local))?;
     for branch in local_branches {
-        let (branch, _) = branch.map_err(|e| e.to_string())?;
-        if let Some(name) = branch.name().map_err(|e| e.to_string())? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
             branches.push(BranchInfo {
                 name: name.to_string(),
                 is_current: name == current_branch,
@@ -626,12 +1323,10 @@ pub fn get_branches(path: &str) -> Result<Vec<BranchInfo>, String> {
     }
 
     // Remote branches
-    let remote_branches = repo
-        .branches(Some(BranchType::Remote))
-        .map_err(|e| e.to_string())?;
+    let remote_branches = repo.branches(Some(BranchType::Remote))?;
     for branch in remote_branches {
-        let (branch, _) = branch.map_err(|e| e.to_string())?;
-        if let Some(name) = branch.name().map_err(|e| e.to_string())? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
             branches.push(BranchInfo {
                 name: name.to_string(),
                 is_current: false,
@@ -643,15 +1338,16 @@ pub fn get_branches(path: &str) -> Result<Vec<BranchInfo>, String> {
     Ok(branches)
 }
 
-pub fn checkout_branch(path: &str, branch_name: &str) -> Result<(), String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-    let obj = repo
-        .revparse_single(&format!("refs/heads/{}", branch_name))
-        .map_err(|e| e.to_string())?;
+pub fn checkout_branch(
+    path: &str,
+    branch_name: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    let obj = repo.revparse_single(&format!("refs/heads/{}", branch_name))?;
 
-    repo.checkout_tree(&obj, None).map_err(|e| e.to_string())?;
-    repo.set_head(&format!("refs/heads/{}", branch_name))
-        .map_err(|e| e.to_string())?;
+    repo.checkout_tree(&obj, Some(&mut checkout_builder(progress)))?;
+    repo.set_head(&format!("refs/heads/{}", branch_name))?;
 
     Ok(())
 }
@@ -683,26 +1379,255 @@ fn get_ahead_behind(repo: &Repository, branch_name: &str) -> (usize, usize) {
     }
 }
 
-pub fn clone_repository(url: &str, path: &str) -> Result<String, String> {
-    Repository::clone(url, path).map_err(|e| e.to_string())?;
+pub fn clone_repository(
+    url: &str,
+    path: &str,
+    options: &CloneOptions,
+    progress: Option<&ProgressReporter>,
+) -> Result<String, GitError> {
+    let remote_name = options.remote_name.as_deref().unwrap_or("origin");
+    let bare = options.bare || options.mirror;
+    println!(
+        "[Rust Clone] Cloning {} into {} (remote: {}, branch: {}, depth: {}, {})",
+        url,
+        path,
+        remote_name,
+        options.branch.as_deref().unwrap_or("default"),
+        options
+            .depth
+            .map_or("full".to_string(), |depth| depth.to_string()),
+        if options.mirror {
+            "mirror"
+        } else if bare {
+            "bare"
+        } else {
+            "with working tree"
+        }
+    );
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(progress, Some(url)));
+    if let Some(depth) = options.depth {
+        if depth <= 0 {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                "Clone depth must be at least 1",
+            ));
+        }
+        fetch_options.depth(depth);
+    }
+
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_options).bare(bare);
+    if !bare {
+        builder.with_checkout(checkout_builder(progress));
+    }
+    if let Some(branch) = &options.branch {
+        builder.branch(branch);
+    }
+    let mirror = options.mirror;
+    builder.remote_create(move |repo, _name, url| {
+        if !mirror {
+            return repo.remote(remote_name, url);
+        }
+        // Like `git clone --mirror`: every ref is copied as is and kept in sync on fetch
+        let remote = repo.remote_with_fetch(remote_name, url, "+refs/*:refs/*")?;
+        repo.config()?
+            .set_bool(&format!("remote.{}.mirror", remote_name), true)?;
+        Ok(remote)
+    });
+
+    let repo = builder.clone(url, Path::new(path))?;
+
+    if options.recurse_submodules && !bare {
+        update_repo_submodules(&repo, None, true, true, progress)?;
+    }
+
     Ok(format!("Successfully cloned repository to {}", path))
 }
 
-pub fn init_repository(path: &str) -> Result<String, String> {
-    println!("[Rust Init] Initializing repository at: {}", path);
-    match Repository::init(path) {
-        Ok(_) => {
-            println!("[Rust Init] Successfully initialized at: {}", path);
-            Ok(format!("Successfully initialized repository at {}", path))
-        }
+pub fn init_repository(path: &str, options: &InitOptions) -> Result<String, GitError> {
+    let branch = options
+        .initial_branch
+        .as_deref()
+        .map(str::trim)
+        .filter(|branch| !branch.is_empty())
+        .map_or_else(default_branch_name, str::to_string);
+    if !git2::Reference::is_valid_name(&format!("refs/heads/{}", branch)) {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!("'{}' is not a valid branch name", branch),
+        ));
+    }
+
+    // Checked before anything is created on disk
+    let mut files: Vec<(&str, String)> = Vec::new();
+    if !options.gitignore.is_empty() {
+        let gitignore = gitignore_for(&options.gitignore).map_err(|name| {
+            GitError::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown .gitignore template: {}", name),
+            )
+        })?;
+        files.push((".gitignore", gitignore));
+    }
+    if options.readme {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Project")
+            .trim_end_matches(".git");
+        files.push(("README.md", format!("# {}\n", name)));
+    }
+    if let Some(license) = &options.license {
+        let holder = git2::Config::open_default()
+            .and_then(|config| config.get_string("user.name"))
+            .unwrap_or_else(|_| "the authors".to_string());
+        let text = license_for(license, &holder).ok_or_else(|| {
+            GitError::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown license: {}", license),
+            )
+        })?;
+        files.push(("LICENSE", text));
+    }
+    if options.bare && !files.is_empty() && !options.initial_commit {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            "A bare repository has no working tree, files can only be added with the first commit",
+        ));
+    }
+    // Initializing an existing repository again is fine, but its history has a first commit already
+    if options.initial_commit
+        && Repository::open(path).is_ok_and(|existing| existing.head().is_ok())
+    {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} is already a repository with commits, it cannot get an initial commit",
+                path
+            ),
+        ));
+    }
+
+    println!(
+        "[Rust Init] Initializing {}repository at: {} (branch: {})",
+        if options.bare { "bare " } else { "" },
+        path,
+        branch
+    );
+    let mut init_options = git2::RepositoryInitOptions::new();
+    init_options
+        .bare(options.bare)
+        .initial_head(&branch)
+        .mkpath(true);
+    let repo = match Repository::init_opts(path, &init_options) {
+        Ok(repo) => repo,
         Err(e) => {
             eprintln!("[Rust Init] Failed to initialize: {}", e);
-            Err(e.to_string())
+            return Err(e.into());
+        }
+    };
+
+    if let Some(workdir) = repo.workdir() {
+        for (name, contents) in &files {
+            let file = workdir.join(name);
+            if file.exists() {
+                println!("[Rust Init] Keeping existing {}", name);
+                continue;
+            }
+            std::fs::write(file, contents)?;
         }
     }
+
+    if options.initial_commit {
+        let tree_id = if repo.is_bare() {
+            let mut tree = repo.treebuilder(None)?;
+            for (name, contents) in &files {
+                let blob = repo.blob(contents.as_bytes())?;
+                tree.insert(name, blob, git2::FileMode::Blob.into())?;
+            }
+            tree.write()?
+        } else {
+            let mut index = repo.index()?;
+            for (name, _) in &files {
+                index.add_path(Path::new(name))?;
+            }
+            index.write()?;
+            index.write_tree()?
+        };
+        let tree = repo.find_tree(tree_id)?;
+        let signature = repo.signature()?;
+        let oid = repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )?;
+        println!("[Rust Init] Created initial commit {}", oid);
+    }
+
+    if let Some(url) = options.remote_url.as_deref().filter(|url| !url.is_empty()) {
+        let name = options.remote_name.as_deref().unwrap_or("origin");
+        repo.remote(name, url)?;
+        println!("[Rust Init] Added remote {} ({})", name, url);
+    }
+
+    println!("[Rust Init] Successfully initialized at: {}", path);
+    Ok(format!("Successfully initialized repository at {}", path))
 }
-pub fn add_remote(path: &str, name: &str, url: &str) -> Result<(), String> {
-    let repo = Repository::open(path).map_err(|e| e.to_string())?;
-    repo.remote(name, url).map_err(|e| e.to_string())?;
-    Ok(())
+
+/// The branch HEAD points at in a repository without commits yet, read from HEAD itself
+/// since there is no commit to resolve it to.
+pub fn unborn_branch_name(repo: &Repository) -> String {
+    repo.find_reference("HEAD")
+        .ok()
+        .and_then(|head| {
+            head.symbolic_target().map(|target| {
+                target
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(target)
+                    .to_string()
+            })
+        })
+        .unwrap_or_else(default_branch_name)
+}
+
+/// The branch new repositories start on: `init.defaultBranch`, otherwise "main".
+pub fn default_branch_name() -> String {
+    git2::Config::open_default()
+        .and_then(|config| config.get_string("init.defaultBranch"))
+        .ok()
+        .filter(|branch| !branch.is_empty())
+        .unwrap_or_else(|| "main".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejection_kind_classifies_remote_status() {
+        assert_eq!(rejection_kind(None), "ok");
+        assert_eq!(rejection_kind(Some("non-fast-forward")), "non_fast_forward");
+        assert_eq!(
+            rejection_kind(Some("rejected (fetch first)")),
+            "non_fast_forward"
+        );
+        assert_eq!(rejection_kind(Some("stale info")), "stale");
+        assert_eq!(
+            rejection_kind(Some("pre-receive hook declined")),
+            "hook_declined"
+        );
+        assert_eq!(
+            rejection_kind(Some("protected branch hook declined")),
+            "hook_declined"
+        );
+        assert_eq!(
+            rejection_kind(Some("deny updating a hidden ref")),
+            "rejected"
+        );
+    }
 }
@@ -1,89 +1,1026 @@
+mod autofetch;
+mod batch;
+mod certificates;
+mod credentials;
+mod discovery;
+mod error;
+mod executor;
 mod git_operations;
+mod operations;
+mod progress;
+mod prompts;
+mod rebase;
+mod remotes;
+mod repo_cache;
+mod ssh;
+mod submodules;
+mod templates;
+mod watcher;
+mod workspace;
 
+use autofetch::AutoFetchSettings;
+use batch::{BatchProgressEvent, BatchRepoResult, BatchSummary};
+use certificates::{HostKeyBroker, HostKeyRequest, HostKeyResponse, HostKeyTrust};
+use credentials::{CredentialBroker, CredentialRequest, CredentialResponse};
+use error::{ErrorKind, GitError};
+use executor::RepoExecutor;
 use git_operations::*;
+use operations::{OperationGuard, OperationRegistry};
+use progress::{ProgressEvent, ProgressReporter};
+use rebase::*;
+use remotes::*;
+use repo_cache::RepoCache;
+use ssh::{SshIdentity, SshSettings, SshSettingsStore};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use submodules::SubmoduleInfo;
+use tauri::{AppHandle, Emitter, Manager, State};
+use templates::InitTemplates;
+use watcher::{RepoChangedEvent, RepoWatchers};
+use workspace::{RepoSettings, Workspace, WorkspaceStore};
+
+/// Builds a reporter emitting `operation-progress` events for a registered operation.
+fn progress_reporter(app: &AppHandle, operation: &OperationGuard) -> ProgressReporter {
+    let app = app.clone();
+    ProgressReporter::new(
+        &operation.id,
+        operation.cancelled.clone(),
+        move |event: &ProgressEvent| {
+            let _ = app.emit("operation-progress", event);
+        },
+    )
+}
+
+/// Builds a reporter for an operation talking to a remote, which may also ask the user
+/// for credentials through `credential-request` events and about unknown SSH host keys
+/// through `host-key-request` events.
+fn network_reporter(
+    app: &AppHandle,
+    operation: &OperationGuard,
+    credentials: &CredentialBroker,
+) -> ProgressReporter {
+    let ssh_settings = app.state::<SshSettingsStore>().get();
+    let trust = app.state::<HostKeyTrust>().inner().clone();
+    let host_keys = app.state::<HostKeyBroker>().inner().clone();
+    let host_key_app = app.clone();
+    let host_key_cancelled = operation.cancelled.clone();
+    let app = app.clone();
+    let credentials = credentials.clone();
+    let cancelled = operation.cancelled.clone();
+    progress_reporter(&app, operation)
+        .with_ssh_settings(ssh_settings)
+        .with_host_key_prompt(trust, move |request: &HostKeyRequest| {
+            host_keys.request(&host_key_cancelled, |request_id| {
+                let request = HostKeyRequest {
+                    request_id: request_id.to_string(),
+                    ..request.clone()
+                };
+                let _ = host_key_app.emit("host-key-request", &request);
+            })
+        })
+        .with_credential_prompt(move |request: &CredentialRequest| {
+            credentials.request(&cancelled, |request_id| {
+                println!(
+                    "[Rust Credentials] Asking for {} credentials for {} ({})",
+                    request.kind, request.url, request_id
+                );
+                let request = CredentialRequest {
+                    request_id: request_id.to_string(),
+                    ..request.clone()
+                };
+                let _ = app.emit("credential-request", &request);
+            })
+        })
+}
+
+/// Starts fetching the workspace repositories that have auto-fetch on in the background,
+/// emitting `remote-refs-changed` when remote-tracking refs move. A repository is skipped
+/// for the round while the user runs an operation on it, and every repository while a
+/// network operation is registered, which may be waiting on a credential prompt.
+fn start_auto_fetch(app: &AppHandle) {
+    let workspace_app = app.clone();
+    let app = app.clone();
+    autofetch::start(
+        move || {
+            let workspace = workspace_app.state::<WorkspaceStore>().get();
+            let paths = workspace
+                .repositories
+                .into_iter()
+                .filter(|repo| !repo.missing && repo.settings.auto_fetch)
+                .map(|repo| repo.path)
+                .collect();
+            (workspace.auto_fetch, paths)
+        },
+        move |path| {
+            let executor = app.state::<RepoExecutor>().inner().clone();
+            if !app.state::<OperationRegistry>().running().is_empty() || executor.is_busy(path) {
+                println!("[Rust AutoFetch] {} is busy, skipping", path);
+                return;
+            }
+
+            // Not registered, the user has nothing to cancel and no prompt is shown
+            let progress =
+                ProgressReporter::new("auto-fetch", Arc::new(AtomicBool::new(false)), |_| {})
+                    .with_ssh_settings(app.state::<SshSettingsStore>().get());
+            let result =
+                tauri::async_runtime::block_on(executor.run(path.to_string(), move |path| {
+                    autofetch::fetch_in_background(path, &progress)
+                }));
+            match result {
+                Ok(Some(event)) => {
+                    let _ = app.emit("remote-refs-changed", &event);
+                }
+                Ok(None) => {}
+                Err(e) => println!("[Rust AutoFetch] Fetching {} failed: {}", path, e.message),
+            }
+        },
+    );
+}
+
+/// Replaces the libgit2 error of an aborted operation with a recognizable message,
+/// and lets the credential helper know whether the credentials used were accepted.
+fn finish_operation<T>(
+    progress: &ProgressReporter,
+    result: Result<T, GitError>,
+) -> Result<T, GitError> {
+    match result {
+        Err(_) if progress.is_cancelled() => Err(GitError::cancelled()),
+        Ok(value) => {
+            progress.settle_credentials(true);
+            Ok(value)
+        }
+        Err(e) => {
+            if matches!(e.kind, ErrorKind::AuthRequired | ErrorKind::AuthFailed) {
+                progress.settle_credentials(false);
+            }
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+async fn get_repo_info_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<RepoInfo, GitError> {
+    let cache = cache.inner().clone();
+    executor
+        .run(path, move |path| {
+            cache.with_repo(path, |repo| get_repo_info(repo, path))
+        })
+        .await
+}
+
+#[tauri::command]
+async fn add_safe_directory_cmd(path: String) -> Result<String, GitError> {
+    executor::blocking(move || discovery::add_safe_directory(&path)).await
+}
+
+#[tauri::command]
+async fn scan_repositories_cmd(
+    path: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<RepoInfo>, GitError> {
+    executor::blocking(move || {
+        discovery::scan_repositories(&path, max_depth.unwrap_or(discovery::DEFAULT_SCAN_DEPTH))
+    })
+    .await
+}
+
+#[tauri::command]
+async fn get_repo_status_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<RepoStatus, GitError> {
+    let cache = cache.inner().clone();
+    executor
+        .run(path, move |path| {
+            cache.with_repo(path, |repo| get_repo_status(repo, path))
+        })
+        .await
+}
 
 #[tauri::command]
-fn get_repo_info_cmd(path: String) -> Result<RepoInfo, String> {
-    get_repo_info(&path)
+async fn stage_file_cmd(
+    path: String,
+    file_path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| stage_file(path, &file_path))
+        .await
 }
 
 #[tauri::command]
-fn get_repo_status_cmd(path: String) -> Result<RepoStatus, String> {
-    get_repo_status(&path)
+async fn unstage_file_cmd(
+    path: String,
+    file_path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| unstage_file(path, &file_path))
+        .await
 }
 
 #[tauri::command]
-fn stage_file_cmd(path: String, file_path: String) -> Result<(), String> {
-    stage_file(&path, &file_path)
+async fn commit_cmd(
+    path: String,
+    message: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<String, GitError> {
+    executor.run(path, move |path| commit(path, &message)).await
 }
 
 #[tauri::command]
-fn unstage_file_cmd(path: String, file_path: String) -> Result<(), String> {
-    unstage_file(&path, &file_path)
+async fn get_commits_cmd(
+    path: String,
+    limit: usize,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<Vec<CommitInfo>, GitError> {
+    let cache = cache.inner().clone();
+    executor
+        .run(path, move |path| {
+            cache.with_repo(path, |repo| get_commits(repo, limit))
+        })
+        .await
 }
 
 #[tauri::command]
-fn commit_cmd(path: String, message: String) -> Result<String, String> {
-    commit(&path, &message)
+async fn get_git_version_cmd() -> Result<GitVersionInfo, GitError> {
+    executor::blocking(get_git_version).await
 }
 
 #[tauri::command]
-fn get_commits_cmd(path: String, limit: usize) -> Result<Vec<CommitInfo>, String> {
-    get_commits(&path, limit)
+async fn get_file_diff_cmd(
+    path: String,
+    file_path: String,
+    staged: bool,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<FileDiff, GitError> {
+    let cache = cache.inner().clone();
+    executor
+        .run(path, move |path| {
+            cache.with_repo(path, |repo| get_file_diff(repo, path, &file_path, staged))
+        })
+        .await
 }
 
 #[tauri::command]
-fn get_git_version_cmd() -> Result<GitVersionInfo, String> {
-    get_git_version()
+async fn pull_cmd(
+    app: AppHandle,
+    path: String,
+    strategy: Option<String>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<PullResult, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(&progress, pull(path, strategy.as_deref(), Some(&progress)))
+        })
+        .await
 }
 
 #[tauri::command]
-fn get_file_diff_cmd(path: String, file_path: String, staged: bool) -> Result<FileDiff, String> {
-    get_file_diff(&path, &file_path, staged)
+#[allow(clippy::too_many_arguments)]
+async fn fetch_cmd(
+    app: AppHandle,
+    path: String,
+    remote: Option<String>,
+    prune: Option<bool>,
+    tags: Option<String>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<FetchResult, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(
+                &progress,
+                fetch(
+                    path,
+                    remote.as_deref(),
+                    prune,
+                    tags.as_deref(),
+                    Some(&progress),
+                ),
+            )
+        })
+        .await
 }
 
+/// Runs `action` ("fetch", "pull" fast-forward only, or "status") on the given repositories,
+/// or on every repository of the workspace that still exists, a few at a time. Emits a
+/// `batch-progress` event as each repository starts and finishes. A failing repository
+/// does not stop the batch, its error is part of the summary.
 #[tauri::command]
-fn pull_cmd(path: String) -> Result<String, String> {
-    pull(&path)
+#[allow(clippy::too_many_arguments)]
+async fn batch_cmd(
+    app: AppHandle,
+    action: String,
+    paths: Option<Vec<String>>,
+    parallelism: Option<usize>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<BatchSummary, GitError> {
+    batch::check_action(&action)?;
+    let paths = paths.unwrap_or_else(|| {
+        workspace
+            .get()
+            .repositories
+            .into_iter()
+            .filter(|repo| !repo.missing)
+            .map(|repo| repo.path)
+            .collect()
+    });
+    let total = paths.len();
+    println!("[Rust Batch] Running {} on {} repositories", action, total);
+
+    let operation = Arc::new(operations.register(operation_id)?);
+    let queue = Arc::new(Mutex::new(
+        paths.into_iter().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let results = Arc::new(Mutex::new(Vec::with_capacity(total)));
+    let completed = Arc::new(AtomicUsize::new(0));
+    let workers = parallelism
+        .unwrap_or(batch::DEFAULT_BATCH_PARALLELISM)
+        .clamp(1, total.max(1));
+
+    let mut handles = Vec::with_capacity(workers);
+    for _ in 0..workers {
+        let app = app.clone();
+        let action = action.clone();
+        let operation = operation.clone();
+        let credentials = credentials.inner().clone();
+        let executor = executor.inner().clone();
+        let queue = queue.clone();
+        let results = results.clone();
+        let completed = completed.clone();
+        handles.push(tauri::async_runtime::spawn(async move {
+            loop {
+                let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                let Some((index, path)) = next else {
+                    break;
+                };
+                let emit = |state: &str, message: Option<String>| {
+                    let _ = app.emit(
+                        "batch-progress",
+                        &BatchProgressEvent {
+                            operation_id: operation.id.clone(),
+                            path: path.clone(),
+                            state: state.to_string(),
+                            completed: completed.load(Ordering::SeqCst),
+                            total,
+                            message,
+                        },
+                    );
+                };
+
+                let result: BatchRepoResult = if operation.cancelled.load(Ordering::SeqCst) {
+                    batch::repo_result(&path, Err(GitError::cancelled()))
+                } else {
+                    emit("running", None);
+                    // A status check never talks to a remote, so it has nothing to ask
+                    let progress = if action == "status" {
+                        progress_reporter(&app, &operation)
+                    } else {
+                        network_reporter(&app, &operation, &credentials)
+                    };
+                    let action = action.clone();
+                    executor
+                        .run(path.clone(), move |path| {
+                            let outcome = finish_operation(
+                                &progress,
+                                batch::run_action(path, &action, Some(&progress)),
+                            );
+                            Ok(batch::repo_result(path, outcome))
+                        })
+                        .await
+                        .unwrap_or_else(|e| batch::repo_result(&path, Err(e)))
+                };
+
+                completed.fetch_add(1, Ordering::SeqCst);
+                match &result.error {
+                    Some(e) => emit("failed", Some(e.message.clone())),
+                    None => emit("done", result.outcome.clone()),
+                }
+                results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push((index, result));
+            }
+        }));
+    }
+    for handle in handles {
+        handle.await.map_err(|e| GitError::other(e.to_string()))?;
+    }
+
+    let mut results = std::mem::take(&mut *results.lock().unwrap_or_else(|e| e.into_inner()));
+    results.sort_by_key(|(index, _)| *index);
+    let summary = BatchSummary::new(
+        &action,
+        results.into_iter().map(|(_, result)| result).collect(),
+    );
+    println!(
+        "[Rust Batch] {} finished: {} succeeded, {} failed",
+        action, summary.succeeded, summary.failed
+    );
+    Ok(summary)
 }
 
 #[tauri::command]
-fn push_cmd(path: String) -> Result<String, String> {
-    push(&path)
+async fn push_cmd(
+    app: AppHandle,
+    path: String,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<String, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(&progress, push(path, Some(&progress)))
+        })
+        .await
 }
 
 #[tauri::command]
-fn push_with_credentials_cmd(
+#[allow(clippy::too_many_arguments)]
+async fn push_with_credentials_cmd(
+    app: AppHandle,
     path: String,
     username: Option<String>,
     password: Option<String>,
-) -> Result<String, String> {
-    push_with_credentials(&path, username, password)
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<String, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(
+                &progress,
+                push_with_credentials(path, username, password, Some(&progress)),
+            )
+        })
+        .await
 }
 
 #[tauri::command]
-fn get_branches_cmd(path: String) -> Result<Vec<BranchInfo>, String> {
-    get_branches(&path)
+#[allow(clippy::too_many_arguments)]
+async fn push_branch_cmd(
+    app: AppHandle,
+    path: String,
+    options: PushOptions,
+    username: Option<String>,
+    password: Option<String>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<PushResult, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(
+                &progress,
+                push_branch(path, &options, username, password, Some(&progress)),
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_branches_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<Vec<BranchInfo>, GitError> {
+    let cache = cache.inner().clone();
+    executor
+        .run(path, move |path| cache.with_repo(path, get_branches))
+        .await
+}
+
+#[tauri::command]
+async fn checkout_branch_cmd(
+    app: AppHandle,
+    path: String,
+    branch_name: String,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = progress_reporter(&app, &operation);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(
+                &progress,
+                checkout_branch(path, &branch_name, Some(&progress)),
+            )
+        })
+        .await
 }
 
 #[tauri::command]
-fn checkout_branch_cmd(path: String, branch_name: String) -> Result<(), String> {
-    checkout_branch(&path, &branch_name)
+#[allow(clippy::too_many_arguments)]
+async fn clone_repository_cmd(
+    app: AppHandle,
+    url: String,
+    path: String,
+    options: Option<CloneOptions>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<String, GitError> {
+    cache.invalidate(&path);
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            let options = options.unwrap_or_default();
+            finish_operation(
+                &progress,
+                clone_repository(&url, path, &options, Some(&progress)),
+            )
+        })
+        .await
 }
 
 #[tauri::command]
-fn clone_repository_cmd(url: String, path: String) -> Result<String, String> {
-    clone_repository(&url, &path)
+async fn init_repository_cmd(
+    path: String,
+    options: Option<InitOptions>,
+    executor: State<'_, RepoExecutor>,
+    cache: State<'_, RepoCache>,
+) -> Result<String, GitError> {
+    cache.invalidate(&path);
+    executor
+        .run(path, move |path| {
+            init_repository(path, &options.unwrap_or_default())
+        })
+        .await
 }
 
 #[tauri::command]
-fn init_repository_cmd(path: String) -> Result<String, String> {
-    init_repository(&path)
+fn get_init_templates_cmd() -> Result<InitTemplates, GitError> {
+    Ok(templates::init_templates())
+}
+
+#[tauri::command]
+fn invalidate_repo_cache_cmd(path: String, cache: State<'_, RepoCache>) -> Result<(), GitError> {
+    cache.invalidate(&path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn add_remote_cmd(
+    path: String,
+    name: String,
+    url: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| add_remote(path, &name, &url))
+        .await
+}
+
+#[tauri::command]
+async fn list_remotes_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<Vec<RemoteInfo>, GitError> {
+    executor.run(path, list_remotes).await
+}
+
+#[tauri::command]
+async fn rename_remote_cmd(
+    path: String,
+    name: String,
+    new_name: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<Vec<String>, GitError> {
+    executor
+        .run(path, move |path| rename_remote(path, &name, &new_name))
+        .await
+}
+
+#[tauri::command]
+async fn remove_remote_cmd(
+    path: String,
+    name: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| remove_remote(path, &name))
+        .await
+}
+
+#[tauri::command]
+async fn set_remote_url_cmd(
+    path: String,
+    name: String,
+    url: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| set_remote_url(path, &name, &url))
+        .await
+}
+
+#[tauri::command]
+async fn set_remote_push_url_cmd(
+    path: String,
+    name: String,
+    push_url: Option<String>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| {
+            set_remote_push_url(path, &name, push_url.as_deref())
+        })
+        .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn show_remote_cmd(
+    app: AppHandle,
+    path: String,
+    name: String,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RemoteDetails, GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(&progress, show_remote(path, &name, Some(&progress)))
+        })
+        .await
+}
+
+#[tauri::command]
+async fn list_submodules_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<Vec<SubmoduleInfo>, GitError> {
+    executor.run(path, submodules::list_submodules).await
+}
+
+#[tauri::command]
+async fn init_submodules_cmd(
+    path: String,
+    paths: Option<Vec<String>>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| {
+            submodules::init_submodules(path, paths.as_deref())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn sync_submodules_cmd(
+    path: String,
+    paths: Option<Vec<String>>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    executor
+        .run(path, move |path| {
+            submodules::sync_submodules(path, paths.as_deref())
+        })
+        .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn update_submodules_cmd(
+    app: AppHandle,
+    path: String,
+    paths: Option<Vec<String>>,
+    init: Option<bool>,
+    recursive: Option<bool>,
+    operation_id: Option<String>,
+    operations: State<'_, OperationRegistry>,
+    credentials: State<'_, CredentialBroker>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<(), GitError> {
+    let operation = operations.register(operation_id)?;
+    let progress = network_reporter(&app, &operation, &credentials);
+    executor
+        .run(path, move |path| {
+            let _operation = operation;
+            finish_operation(
+                &progress,
+                submodules::update_submodules(
+                    path,
+                    paths.as_deref(),
+                    init.unwrap_or(true),
+                    recursive.unwrap_or(false),
+                    Some(&progress),
+                ),
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+async fn rebase_onto_cmd(
+    path: String,
+    onto: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RebaseStatus, GitError> {
+    executor
+        .run(path, move |path| rebase_onto(path, &onto))
+        .await
+}
+
+#[tauri::command]
+async fn rebase_continue_cmd(
+    path: String,
+    message: Option<String>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RebaseStatus, GitError> {
+    executor
+        .run(path, move |path| rebase_continue(path, message.as_deref()))
+        .await
+}
+
+#[tauri::command]
+async fn rebase_skip_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RebaseStatus, GitError> {
+    executor.run(path, rebase_skip).await
+}
+
+#[tauri::command]
+async fn rebase_abort_cmd(path: String, executor: State<'_, RepoExecutor>) -> Result<(), GitError> {
+    executor.run(path, rebase_abort).await
+}
+
+#[tauri::command]
+async fn get_rebase_status_cmd(
+    path: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RebaseStatus, GitError> {
+    executor.run(path, get_rebase_status).await
+}
+
+#[tauri::command]
+async fn get_rebase_plan_cmd(
+    path: String,
+    base: String,
+    executor: State<'_, RepoExecutor>,
+) -> Result<Vec<RebasePlanStep>, GitError> {
+    executor
+        .run(path, move |path| get_rebase_plan(path, &base))
+        .await
+}
+
+#[tauri::command]
+async fn interactive_rebase_cmd(
+    path: String,
+    base: String,
+    steps: Vec<RebasePlanStep>,
+    executor: State<'_, RepoExecutor>,
+) -> Result<RebaseStatus, GitError> {
+    executor
+        .run(path, move |path| interactive_rebase(path, &base, steps))
+        .await
+}
+
+#[tauri::command]
+fn cancel_operation_cmd(
+    operation_id: String,
+    operations: State<'_, OperationRegistry>,
+) -> Result<bool, GitError> {
+    Ok(operations.cancel(&operation_id))
+}
+
+#[tauri::command]
+fn respond_credentials_cmd(
+    request_id: String,
+    response: Option<CredentialResponse>,
+    credentials: State<'_, CredentialBroker>,
+) -> Result<bool, GitError> {
+    Ok(credentials.respond(&request_id, response))
+}
+
+#[tauri::command]
+fn respond_host_key_cmd(
+    request_id: String,
+    response: Option<HostKeyResponse>,
+    host_keys: State<'_, HostKeyBroker>,
+) -> Result<bool, GitError> {
+    Ok(host_keys.respond(&request_id, response))
+}
+
+#[tauri::command]
+fn get_ssh_settings_cmd(ssh: State<'_, SshSettingsStore>) -> Result<SshSettings, GitError> {
+    Ok(ssh.get())
+}
+
+#[tauri::command]
+fn set_ssh_settings_cmd(
+    settings: SshSettings,
+    ssh: State<'_, SshSettingsStore>,
+) -> Result<(), GitError> {
+    ssh.set(settings)
+}
+
+#[tauri::command]
+fn get_ssh_identities_cmd(
+    url: String,
+    ssh: State<'_, SshSettingsStore>,
+) -> Result<Vec<SshIdentity>, GitError> {
+    let host = ssh::host_from_url(&url).unwrap_or_default();
+    let user = ssh::user_from_url(&url).unwrap_or_else(|| "git".to_string());
+    Ok(ssh::identities_for_host(&host, &user, &ssh.get()).0)
+}
+
+#[tauri::command]
+fn get_workspace_cmd(workspace: State<'_, WorkspaceStore>) -> Result<Workspace, GitError> {
+    Ok(workspace.get())
+}
+
+#[tauri::command]
+fn validate_workspace_cmd(workspace: State<'_, WorkspaceStore>) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| {
+        workspace.validate();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn add_workspace_repositories_cmd(
+    paths: Vec<String>,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| {
+        workspace.add(&paths);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn remove_workspace_repository_cmd(
+    path: String,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| {
+        workspace.remove(&path);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn reorder_workspace_repositories_cmd(
+    paths: Vec<String>,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| {
+        workspace.reorder(&paths);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn update_workspace_repository_cmd(
+    path: String,
+    alias: Option<String>,
+    group: Option<String>,
+    settings: RepoSettings,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| workspace.update(&path, alias, group, settings))
+}
+
+#[tauri::command]
+fn mark_repository_opened_cmd(
+    path: String,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| workspace.mark_opened(&path))
+}
+
+#[tauri::command]
+fn set_workspace_groups_cmd(
+    groups: Vec<String>,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| {
+        workspace.set_groups(groups);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn rename_workspace_group_cmd(
+    name: String,
+    new_name: String,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<Workspace, GitError> {
+    workspace.update(|workspace| workspace.rename_group(&name, &new_name))
+}
+
+#[tauri::command]
+fn get_auto_fetch_settings_cmd(
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<AutoFetchSettings, GitError> {
+    Ok(workspace.get().auto_fetch)
+}
+
+#[tauri::command]
+fn set_auto_fetch_settings_cmd(
+    settings: AutoFetchSettings,
+    workspace: State<'_, WorkspaceStore>,
+) -> Result<(), GitError> {
+    if settings.interval_secs < autofetch::MIN_INTERVAL_SECS {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The fetch interval must be at least {} seconds",
+                autofetch::MIN_INTERVAL_SECS
+            ),
+        ));
+    }
+    workspace.update(|workspace| {
+        workspace.auto_fetch = settings;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_running_operations_cmd(
+    operations: State<'_, OperationRegistry>,
+) -> Result<Vec<String>, GitError> {
+    Ok(operations.running())
+}
+
+#[tauri::command]
+async fn watch_repository_cmd(
+    app: AppHandle,
+    path: String,
+    watchers: State<'_, RepoWatchers>,
+) -> Result<(), GitError> {
+    let watchers = watchers.inner().clone();
+    executor::blocking(move || {
+        watchers.watch(&path, move |event: &RepoChangedEvent| {
+            let _ = app.emit("repo-changed", event);
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+fn unwatch_repository_cmd(
+    path: String,
+    watchers: State<'_, RepoWatchers>,
+) -> Result<bool, GitError> {
+    Ok(watchers.unwatch(&path))
 }
 
 #[tauri::command]
-fn add_remote_cmd(path: String, name: String, url: String) -> Result<(), String> {
-    add_remote(&path, &name, &url)
+fn get_watched_repositories_cmd(
+    watchers: State<'_, RepoWatchers>,
+) -> Result<Vec<String>, GitError> {
+    Ok(watchers.watched())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -91,6 +1028,13 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .manage(OperationRegistry::new())
+        .manage(RepoExecutor::new())
+        .manage(RepoCache::new())
+        .manage(CredentialBroker::new("cred"))
+        .manage(HostKeyBroker::new("hostkey"))
+        .manage(HostKeyTrust::new())
+        .manage(RepoWatchers::new())
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -99,25 +1043,78 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            // Before anything below touches libgit2 or starts a thread that does
+            certificates::configure_ca_bundle();
+            let config_dir = app.path().app_config_dir().ok();
+            let ssh_settings = config_dir.as_ref().map(|dir| dir.join("ssh.json"));
+            app.manage(SshSettingsStore::load(ssh_settings));
+            let workspace = config_dir.as_ref().map(|dir| dir.join("workspace.json"));
+            app.manage(WorkspaceStore::load(workspace));
+            start_auto_fetch(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_repo_info_cmd,
             get_repo_status_cmd,
+            add_safe_directory_cmd,
+            scan_repositories_cmd,
             stage_file_cmd,
             unstage_file_cmd,
             commit_cmd,
             get_commits_cmd,
             pull_cmd,
+            fetch_cmd,
             push_cmd,
             get_git_version_cmd,
             push_with_credentials_cmd,
+            push_branch_cmd,
             get_file_diff_cmd,
             get_branches_cmd,
             checkout_branch_cmd,
             clone_repository_cmd,
             init_repository_cmd,
-            add_remote_cmd
+            get_init_templates_cmd,
+            invalidate_repo_cache_cmd,
+            add_remote_cmd,
+            list_remotes_cmd,
+            rename_remote_cmd,
+            remove_remote_cmd,
+            set_remote_url_cmd,
+            set_remote_push_url_cmd,
+            show_remote_cmd,
+            list_submodules_cmd,
+            init_submodules_cmd,
+            sync_submodules_cmd,
+            update_submodules_cmd,
+            rebase_onto_cmd,
+            rebase_continue_cmd,
+            rebase_skip_cmd,
+            rebase_abort_cmd,
+            get_rebase_status_cmd,
+            get_rebase_plan_cmd,
+            interactive_rebase_cmd,
+            cancel_operation_cmd,
+            get_workspace_cmd,
+            validate_workspace_cmd,
+            add_workspace_repositories_cmd,
+            remove_workspace_repository_cmd,
+            reorder_workspace_repositories_cmd,
+            update_workspace_repository_cmd,
+            mark_repository_opened_cmd,
+            set_workspace_groups_cmd,
+            rename_workspace_group_cmd,
+            batch_cmd,
+            get_auto_fetch_settings_cmd,
+            set_auto_fetch_settings_cmd,
+            get_running_operations_cmd,
+            respond_credentials_cmd,
+            respond_host_key_cmd,
+            get_ssh_settings_cmd,
+            set_ssh_settings_cmd,
+            get_ssh_identities_cmd,
+            watch_repository_cmd,
+            unwatch_repository_cmd,
+            get_watched_repositories_cmd
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
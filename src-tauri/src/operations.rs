@@ -0,0 +1,91 @@
+use crate::error::{ErrorKind, GitError};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Long-running operations currently in flight, keyed by operation id.
+/// Kept in Tauri managed state so `cancel_operation_cmd` can reach them.
+#[derive(Clone, Default)]
+pub struct OperationRegistry {
+    operations: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Registration of a running operation, removed from the registry when dropped.
+pub struct OperationGuard {
+    registry: OperationRegistry,
+    pub id: String,
+    pub cancelled: Arc<AtomicBool>,
+}
+
+impl OperationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an operation under the id chosen by the frontend, or a generated one.
+    /// An id that is still running is refused, cancelling it would reach only one of them.
+    pub fn register(&self, operation_id: Option<String>) -> Result<OperationGuard, GitError> {
+        let id = operation_id
+            .unwrap_or_else(|| format!("op-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        match self.lock().entry(id.clone()) {
+            Entry::Occupied(_) => {
+                return Err(GitError::new(
+                    ErrorKind::InvalidInput,
+                    format!("Operation {} is already running", id),
+                ))
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(cancelled.clone());
+            }
+        }
+        println!("[Rust Operations] Started operation {}", id);
+
+        Ok(OperationGuard {
+            registry: self.clone(),
+            id,
+            cancelled,
+        })
+    }
+
+    /// Flags an operation as cancelled. Returns false if no such operation is running.
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.lock().get(operation_id) {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::SeqCst);
+                println!("[Rust Operations] Cancelling operation {}", operation_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn running(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+        match self.operations.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let mut operations = self.registry.lock();
+        // Only remove the entry this guard registered
+        if operations
+            .get(&self.id)
+            .is_some_and(|cancelled| Arc::ptr_eq(cancelled, &self.cancelled))
+        {
+            operations.remove(&self.id);
+        }
+        drop(operations);
+        println!("[Rust Operations] Finished operation {}", self.id);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::certificates::{HostKeyRequest, HostKeyResponse, HostKeyTrust, HostKeyVerifier};
+use crate::credentials::{
+    credential_helper, CredentialHandler, CredentialPromptFn, CredentialRequest,
+    CredentialResponse, OfferedCredentials,
+};
+use crate::ssh::{port_from_url, SshSettings};
+use git2::build::CheckoutBuilder;
+use git2::{CheckoutNotificationType, RemoteCallbacks};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimum delay between two events of the same stage, libgit2 reports far more often.
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub stage: String, // "receiving", "resolving", "pushing", "checkout", "remote"
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+    pub message: Option<String>,
+}
+
+type EmitFn = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Forwards libgit2 progress callbacks of one operation to a sink (a Tauri event in the app),
+/// and aborts the operation from those callbacks once it has been cancelled.
+pub struct ProgressReporter {
+    operation_id: String,
+    cancelled: Arc<AtomicBool>,
+    emit: EmitFn,
+    last_emit: Mutex<Option<(String, Instant)>>,
+    credential_prompt: Option<CredentialPromptFn>,
+    offered_credentials: Mutex<Option<OfferedCredentials>>,
+    ssh_settings: SshSettings,
+    host_keys: Option<HostKeyVerifier>,
+}
+
+impl ProgressReporter {
+    pub fn new<F>(operation_id: &str, cancelled: Arc<AtomicBool>, emit: F) -> Self
+    where
+        F: Fn(&ProgressEvent) + Send + Sync + 'static,
+    {
+        ProgressReporter {
+            operation_id: operation_id.to_string(),
+            cancelled,
+            emit: Box::new(emit),
+            last_emit: Mutex::new(None),
+            credential_prompt: None,
+            offered_credentials: Mutex::new(None),
+            ssh_settings: SshSettings::default(),
+            host_keys: None,
+        }
+    }
+
+    /// Lets the operation ask the user for credentials when the remote requires them.
+    pub fn with_credential_prompt<F>(mut self, prompt: F) -> Self
+    where
+        F: Fn(&CredentialRequest) -> Option<CredentialResponse> + Send + Sync + 'static,
+    {
+        self.credential_prompt = Some(Box::new(prompt));
+        self
+    }
+
+    /// Checks SSH host keys against known_hosts and lets the user decide about the ones
+    /// it does not list. Without it libgit2 rejects unknown hosts itself.
+    pub fn with_host_key_prompt<F>(mut self, trust: HostKeyTrust, prompt: F) -> Self
+    where
+        F: Fn(&HostKeyRequest) -> Option<HostKeyResponse> + Send + Sync + 'static,
+    {
+        self.host_keys = Some(HostKeyVerifier::new(trust, Box::new(prompt)));
+        self
+    }
+
+    /// Key files to offer when the remote asks for SSH authentication.
+    pub fn with_ssh_settings(mut self, settings: SshSettings) -> Self {
+        self.ssh_settings = settings;
+        self
+    }
+
+    pub fn ssh_settings(&self) -> SshSettings {
+        self.ssh_settings.clone()
+    }
+
+    pub fn credential_prompt(&self) -> Option<&CredentialPromptFn> {
+        self.credential_prompt.as_ref()
+    }
+
+    pub fn offered_credentials(&self) -> Option<OfferedCredentials> {
+        self.lock_offered().clone()
+    }
+
+    pub fn set_offered_credentials(&self, offered: Option<OfferedCredentials>) {
+        *self.lock_offered() = offered;
+    }
+
+    /// Reports the outcome of the operation to the credential helper: credentials that
+    /// worked are stored, ones the remote refused are erased.
+    pub fn settle_credentials(&self, accepted: bool) {
+        if let Some(offered) = self.lock_offered().take() {
+            credential_helper(if accepted { "approve" } else { "reject" }, &offered);
+        }
+    }
+
+    pub fn operation_id(&self) -> &str {
+        &self.operation_id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn report(&self, stage: &str, current: usize, total: usize, bytes: usize) {
+        let finished = total > 0 && current >= total;
+        if !finished && !self.should_emit(stage) {
+            return;
+        }
+
+        (self.emit)(&ProgressEvent {
+            operation_id: self.operation_id.clone(),
+            stage: stage.to_string(),
+            current,
+            total,
+            bytes,
+            message: None,
+        });
+    }
+
+    pub fn message(&self, message: &str) {
+        let message = message.trim();
+        if message.is_empty() || !self.should_emit("remote") {
+            return;
+        }
+
+        (self.emit)(&ProgressEvent {
+            operation_id: self.operation_id.clone(),
+            stage: "remote".to_string(),
+            current: 0,
+            total: 0,
+            bytes: 0,
+            message: Some(message.to_string()),
+        });
+    }
+
+    /// Remote callbacks reporting transfer, push and side-band progress, and answering
+    /// credential requests and host key checks. Returning false from the transfer and side-band callbacks
+    /// makes libgit2 abort. `url` is the remote being connected to, for the port of its host key.
+    pub fn remote_callbacks(&self, url: Option<&str>) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+
+        let mut credentials = CredentialHandler::new(Some(self), None);
+        callbacks.credentials(move |url, username_from_url, allowed| {
+            credentials.credentials(url, username_from_url, allowed)
+        });
+
+        if let Some(host_keys) = &self.host_keys {
+            let port = url.and_then(port_from_url);
+            callbacks.certificate_check(move |cert, host| {
+                host_keys.check(cert, host, port, &self.operation_id)
+            });
+        }
+
+        callbacks.transfer_progress(move |stats| {
+            if stats.received_objects() < stats.total_objects() {
+                self.report(
+                    "receiving",
+                    stats.received_objects(),
+                    stats.total_objects(),
+                    stats.received_bytes(),
+                );
+            } else {
+                self.report(
+                    "resolving",
+                    stats.indexed_deltas(),
+                    stats.total_deltas(),
+                    stats.received_bytes(),
+                );
+            }
+            !self.is_cancelled()
+        });
+        callbacks.push_transfer_progress(move |current, total, bytes| {
+            self.report("pushing", current, total, bytes);
+        });
+        callbacks.sideband_progress(move |data| {
+            // Remote output uses \r to redraw the same line
+            let text = String::from_utf8_lossy(data);
+            if let Some(line) = text
+                .split(['\r', '\n'])
+                .rev()
+                .find(|l| !l.trim().is_empty())
+            {
+                self.message(line);
+            }
+            !self.is_cancelled()
+        });
+        callbacks.push_negotiation(move |_updates| {
+            if self.is_cancelled() {
+                Err(git2::Error::from_str("Operation cancelled"))
+            } else {
+                Ok(())
+            }
+        });
+
+        callbacks
+    }
+
+    /// Checkout options reporting the number of files written.
+    /// Cancellation is only honoured before files start being written.
+    pub fn checkout_builder(&self) -> CheckoutBuilder<'_> {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.progress(move |_path, current, total| {
+            self.report("checkout", current, total, 0);
+        });
+        checkout.notify_on(CheckoutNotificationType::UPDATED);
+        checkout.notify(move |_kind, _path, _baseline, _target, _workdir| !self.is_cancelled());
+        checkout
+    }
+
+    fn lock_offered(&self) -> std::sync::MutexGuard<'_, Option<OfferedCredentials>> {
+        match self.offered_credentials.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn should_emit(&self, stage: &str) -> bool {
+        let mut last_emit = match self.last_emit.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let now = Instant::now();
+        let emit = match last_emit.as_ref() {
+            Some((last_stage, at)) => {
+                last_stage != stage || now.duration_since(*at) >= EMIT_INTERVAL
+            }
+            None => true,
+        };
+        if emit {
+            *last_emit = Some((stage.to_string(), now));
+        }
+        emit
+    }
+}
+
+/// Remote callbacks for an operation that may or may not report progress.
+pub fn remote_callbacks<'a>(
+    progress: Option<&'a ProgressReporter>,
+    url: Option<&str>,
+) -> RemoteCallbacks<'a> {
+    match progress {
+        Some(progress) => progress.remote_callbacks(url),
+        None => RemoteCallbacks::new(),
+    }
+}
+
+/// Checkout options for an operation that may or may not report progress.
+pub fn checkout_builder(progress: Option<&ProgressReporter>) -> CheckoutBuilder<'_> {
+    match progress {
+        Some(progress) => progress.checkout_builder(),
+        None => CheckoutBuilder::new(),
+    }
+}
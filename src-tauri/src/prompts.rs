@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+/// A prompt left unanswered this long fails the operation.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often a waiting prompt checks whether its operation was cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+type PendingPrompts<T> = HashMap<String, mpsc::Sender<Option<T>>>;
+
+/// Questions a running operation asked the frontend, waiting for an answer of type `T`,
+/// keyed by request id. Kept in Tauri managed state so a command can answer them.
+/// Only one question is shown at a time, operations running in parallel wait their turn.
+pub struct PromptBroker<T> {
+    prefix: &'static str,
+    pending: Arc<Mutex<PendingPrompts<T>>>,
+    next_id: Arc<AtomicU64>,
+    turn: Arc<Mutex<()>>,
+}
+
+impl<T> Clone for PromptBroker<T> {
+    fn clone(&self) -> Self {
+        PromptBroker {
+            prefix: self.prefix,
+            pending: self.pending.clone(),
+            next_id: self.next_id.clone(),
+            turn: self.turn.clone(),
+        }
+    }
+}
+
+impl<T> PromptBroker<T> {
+    /// `prefix` starts every request id, which keeps ids of different brokers apart.
+    pub fn new(prefix: &'static str) -> Self {
+        PromptBroker {
+            prefix,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+            turn: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Waits until no other prompt is shown, then hands a new request id to `emit` and
+    /// blocks until the frontend answers it, the operation is cancelled or the prompt
+    /// times out. None means no answer was given.
+    pub fn request<E>(&self, cancelled: &AtomicBool, emit: E) -> Option<T>
+    where
+        E: FnOnce(&str),
+    {
+        let _turn = loop {
+            if cancelled.load(Ordering::SeqCst) {
+                return None;
+            }
+            match self.turn.try_lock() {
+                Ok(guard) => break guard,
+                Err(TryLockError::Poisoned(poisoned)) => break poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => std::thread::sleep(CANCEL_POLL_INTERVAL),
+            }
+        };
+
+        let request_id = format!(
+            "{}-{}",
+            self.prefix,
+            self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let (sender, receiver) = mpsc::channel();
+        self.lock().insert(request_id.clone(), sender);
+
+        emit(&request_id);
+
+        let started = Instant::now();
+        let response = loop {
+            if cancelled.load(Ordering::SeqCst) || started.elapsed() >= PROMPT_TIMEOUT {
+                break None;
+            }
+            match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+                Ok(response) => break response,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break None,
+            }
+        };
+
+        self.lock().remove(&request_id);
+        response
+    }
+
+    /// Answers a waiting prompt. Returns false if no such prompt is waiting.
+    pub fn respond(&self, request_id: &str, response: Option<T>) -> bool {
+        match self.lock().remove(request_id) {
+            Some(sender) => sender.send(response).is_ok(),
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PendingPrompts<T>> {
+        match self.pending.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
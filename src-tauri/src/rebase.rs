@@ -0,0 +1,866 @@
+use crate::discovery::open_repository;
+use crate::error::{ErrorKind, GitError};
+use git2::{
+    AnnotatedCommit, Commit, Oid, Rebase, RebaseOptions, Repository, RepositoryState, Sort,
+    StatusOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const INTERACTIVE_STATE_DIR: &str = "gitgud-rebase";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebaseStatus {
+    pub state: String, // "none", "completed", "conflicts", "stopped", "edit", "reword"
+    pub current_step: usize,
+    pub total_steps: usize,
+    pub current_commit: Option<String>,
+    pub current_message: Option<String>,
+    pub onto: Option<String>,
+    pub conflicted_files: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebasePlanStep {
+    pub action: String, // "pick", "reword", "edit", "squash", "fixup", "drop"
+    pub commit: String,
+    pub message: Option<String>,
+}
+
+/// Progress of an interactive rebase, persisted in the git directory between commands.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct InteractiveRebaseState {
+    head_name: String,
+    orig_head: String,
+    onto: String,
+    steps: Vec<RebasePlanStep>,
+    current: usize,
+    stopped: Option<String>,
+    // The first commit this rebase created, until then HEAD is still the base commit
+    #[serde(default)]
+    first_rewritten: Option<String>,
+}
+
+pub fn rebase_onto(path: &str, onto: &str) -> Result<RebaseStatus, GitError> {
+    let repo = open_repository(path)?;
+    ensure_can_rebase(&repo)?;
+
+    let head = repo.head()?;
+    let branch = repo.reference_to_annotated_commit(&head)?;
+    let upstream = resolve_annotated_commit(&repo, onto)?;
+
+    println!(
+        "[Rust Rebase] Rebasing {} onto {}",
+        head.shorthand().unwrap_or("HEAD"),
+        onto
+    );
+
+    let mut rebase_opts = RebaseOptions::new();
+    let mut rebase = repo.rebase(Some(&branch), Some(&upstream), None, Some(&mut rebase_opts))?;
+
+    run_rebase(&repo, &mut rebase, Some(onto.to_string()))
+}
+
+pub fn rebase_continue(path: &str, message: Option<&str>) -> Result<RebaseStatus, GitError> {
+    let repo = open_repository(path)?;
+    if let Some(state) = load_interactive_state(&repo)? {
+        return interactive_continue(&repo, state, message);
+    }
+
+    let mut rebase = repo.open_rebase(None)?;
+
+    let index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(GitError::new(
+            ErrorKind::Conflicts,
+            "Cannot continue: resolve and stage all conflicted files first",
+        ));
+    }
+
+    // Commit the step that stopped on conflicts, now that it has been resolved
+    if let Some(current) = rebase.operation_current() {
+        let original_id = rebase
+            .nth(current)
+            .map(|op| op.id())
+            .ok_or_else(|| "Rebase operation not found".to_string())?;
+        commit_operation(&repo, &mut rebase, original_id, message)?;
+    }
+
+    run_rebase(&repo, &mut rebase, read_onto_name(&repo))
+}
+
+pub fn rebase_skip(path: &str) -> Result<RebaseStatus, GitError> {
+    let repo = open_repository(path)?;
+    if let Some(state) = load_interactive_state(&repo)? {
+        return interactive_skip(&repo, state);
+    }
+
+    let mut rebase = repo.open_rebase(None)?;
+
+    // Throw away the changes of the current step before moving on
+    reset_hard_to_head(&repo)?;
+
+    run_rebase(&repo, &mut rebase, read_onto_name(&repo))
+}
+
+pub fn rebase_abort(path: &str) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    if let Some(state) = load_interactive_state(&repo)? {
+        return interactive_abort(&repo, state);
+    }
+
+    let mut rebase = repo.open_rebase(None)?;
+    rebase.abort()?;
+    println!("[Rust Rebase] Aborted rebase in {}", path);
+    Ok(())
+}
+
+pub fn get_rebase_status(path: &str) -> Result<RebaseStatus, GitError> {
+    let repo = open_repository(path)?;
+    if let Some(state) = load_interactive_state(&repo)? {
+        return interactive_status(&repo, &state);
+    }
+
+    match repo.state() {
+        RepositoryState::Rebase | RepositoryState::RebaseMerge => {}
+        _ => {
+            return Ok(RebaseStatus {
+                state: "none".to_string(),
+                current_step: 0,
+                total_steps: 0,
+                current_commit: None,
+                current_message: None,
+                onto: None,
+                conflicted_files: Vec::new(),
+            })
+        }
+    }
+
+    let mut rebase = repo.open_rebase(None)?;
+    stopped_status(&repo, &mut rebase, read_onto_name(&repo))
+}
+
+/// Applies the remaining operations of a rebase, stopping at the first conflict.
+fn run_rebase(
+    repo: &Repository,
+    rebase: &mut Rebase,
+    onto: Option<String>,
+) -> Result<RebaseStatus, GitError> {
+    while let Some(op) = rebase.next() {
+        let original_id = op?.id();
+
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            println!("[Rust Rebase] Stopped on conflicts at {}", original_id);
+            return stopped_status(repo, rebase, onto);
+        }
+
+        commit_operation(repo, rebase, original_id, None)?;
+    }
+
+    let committer = repo.signature()?;
+    rebase.finish(Some(&committer))?;
+
+    let total_steps = rebase.len();
+    println!("[Rust Rebase] Rebase completed ({} commits)", total_steps);
+
+    Ok(RebaseStatus {
+        state: "completed".to_string(),
+        current_step: total_steps,
+        total_steps,
+        current_commit: None,
+        current_message: None,
+        onto,
+        conflicted_files: Vec::new(),
+    })
+}
+
+/// Commits the current operation, keeping the author of the original commit.
+fn commit_operation(
+    repo: &Repository,
+    rebase: &mut Rebase,
+    original_id: Oid,
+    message: Option<&str>,
+) -> Result<(), GitError> {
+    let original = repo.find_commit(original_id)?;
+    let author = original.author();
+    let committer = repo.signature()?;
+
+    match rebase.commit(Some(&author), &committer, message) {
+        Ok(_) => Ok(()),
+        // The patch was already present upstream, nothing to commit
+        Err(e) if e.code() == git2::ErrorCode::Applied => {
+            println!(
+                "[Rust Rebase] Skipping already applied commit {}",
+                original_id
+            );
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn stopped_status(
+    repo: &Repository,
+    rebase: &mut Rebase,
+    onto: Option<String>,
+) -> Result<RebaseStatus, GitError> {
+    let total_steps = rebase.len();
+    let current = rebase.operation_current();
+    let current_id = current.and_then(|i| rebase.nth(i)).map(|op| op.id());
+
+    let current_message = current_id
+        .and_then(|id| repo.find_commit(id).ok())
+        .and_then(|commit| commit.summary().map(|s| s.to_string()));
+
+    let conflicted_files = conflicted_paths(repo)?;
+    let state = if conflicted_files.is_empty() {
+        "stopped"
+    } else {
+        "conflicts"
+    };
+
+    Ok(RebaseStatus {
+        state: state.to_string(),
+        current_step: current.map(|i| i + 1).unwrap_or(0),
+        total_steps,
+        current_commit: current_id.map(|id| id.to_string()),
+        current_message,
+        onto,
+        conflicted_files,
+    })
+}
+
+pub(crate) fn conflicted_paths(repo: &Repository) -> Result<Vec<String>, GitError> {
+    let index = repo.index()?;
+    let mut paths = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Resolves a branch name, remote-tracking branch or any revision to an annotated commit.
+pub(crate) fn resolve_annotated_commit<'repo>(
+    repo: &'repo Repository,
+    spec: &str,
+) -> Result<AnnotatedCommit<'repo>, GitError> {
+    if let Ok(reference) = repo.resolve_reference_from_short_name(spec) {
+        return repo
+            .reference_to_annotated_commit(&reference)
+            .map_err(GitError::from);
+    }
+
+    let commit = repo
+        .revparse_single(spec)
+        .and_then(|obj| obj.peel_to_commit())?;
+    repo.find_annotated_commit(commit.id())
+        .map_err(GitError::from)
+}
+
+fn read_onto_name(repo: &Repository) -> Option<String> {
+    std::fs::read_to_string(repo.path().join("rebase-merge").join("onto_name"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// Lists the commits after `base` up to HEAD, oldest first, as a default all-pick plan.
+pub fn get_rebase_plan(path: &str, base: &str) -> Result<Vec<RebasePlanStep>, GitError> {
+    let repo = open_repository(path)?;
+    let base_commit = resolve_commit(&repo, base)?;
+
+    let commits = commits_since(&repo, base_commit.id())?;
+    let mut steps = Vec::new();
+    for oid in commits {
+        steps.push(RebasePlanStep {
+            action: "pick".to_string(),
+            commit: oid.to_string(),
+            message: None,
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Rewrites the commits after `base` up to HEAD following the given todo plan.
+pub fn interactive_rebase(
+    path: &str,
+    base: &str,
+    steps: Vec<RebasePlanStep>,
+) -> Result<RebaseStatus, GitError> {
+    let repo = open_repository(path)?;
+    ensure_can_rebase(&repo)?;
+
+    let head = repo.head()?;
+    let head_name = head
+        .name()
+        .ok_or_else(|| "Invalid branch name".to_string())?
+        .to_string();
+    let orig_head = head.peel_to_commit()?.id();
+    let base_commit = resolve_commit(&repo, base)?;
+
+    validate_plan(&repo, base_commit.id(), &steps)?;
+
+    println!(
+        "[Rust Rebase] Interactive rebase of {} onto {} ({} steps)",
+        head_name,
+        base_commit.id(),
+        steps.len()
+    );
+
+    let state = InteractiveRebaseState {
+        head_name,
+        orig_head: orig_head.to_string(),
+        onto: base_commit.id().to_string(),
+        steps,
+        current: 0,
+        stopped: None,
+        first_rewritten: None,
+    };
+    save_interactive_state(&repo, &state)?;
+
+    repo.set_head_detached(base_commit.id())?;
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+
+    run_interactive(&repo, state)
+}
+
+fn validate_plan(repo: &Repository, base: Oid, steps: &[RebasePlanStep]) -> Result<(), GitError> {
+    let in_range = commits_since(repo, base)?;
+    let mut seen = Vec::new();
+    let mut has_previous = false;
+
+    for step in steps {
+        let oid = Oid::from_str(&step.commit)?;
+        if !in_range.contains(&oid) {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                format!("Commit {} is not between the base and HEAD", step.commit),
+            ));
+        }
+        if seen.contains(&oid) {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                format!("Commit {} appears more than once", step.commit),
+            ));
+        }
+        seen.push(oid);
+
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() > 1 {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot rebase merge commit {:.7}", commit.id()),
+            ));
+        }
+
+        match step.action.as_str() {
+            "pick" | "reword" | "edit" => has_previous = true,
+            "squash" | "fixup" if !has_previous => {
+                return Err(GitError::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Cannot {} {}: there is no previous commit",
+                        step.action, step.commit
+                    ),
+                ));
+            }
+            "squash" | "fixup" | "drop" => {}
+            other => {
+                return Err(GitError::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown rebase action: {}", other),
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the remaining steps of an interactive rebase, stopping on conflicts,
+/// `edit` steps and `reword` steps without a new message.
+fn run_interactive(
+    repo: &Repository,
+    mut state: InteractiveRebaseState,
+) -> Result<RebaseStatus, GitError> {
+    while state.current < state.steps.len() {
+        let step = state.steps[state.current].clone();
+        if step.action == "drop" {
+            println!("[Rust Rebase] Dropping {}", step.commit);
+            state.current += 1;
+            continue;
+        }
+
+        let oid = Oid::from_str(&step.commit)?;
+        let commit = repo.find_commit(oid)?;
+        repo.cherrypick(&commit, None)?;
+
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            println!("[Rust Rebase] Stopped on conflicts at {}", step.commit);
+            state.stopped = Some("conflicts".to_string());
+            save_interactive_state(repo, &state)?;
+            return interactive_status(repo, &state);
+        }
+
+        let pause = commit_step(repo, &step, None, &mut state.first_rewritten)?;
+        state.current += 1;
+        if let Some(pause) = pause {
+            println!("[Rust Rebase] Paused for {} at {}", pause, step.commit);
+            state.stopped = Some(pause);
+            save_interactive_state(repo, &state)?;
+            return interactive_status(repo, &state);
+        }
+    }
+
+    finish_interactive(repo, &state)
+}
+
+/// Records the applied changes of a step as a commit on top of the rewritten history.
+/// Returns the reason to pause, if the step asks for one. `first_rewritten` is set once
+/// the rebase created a commit, a squash or fixup before that would fold into the base
+/// commit and is applied as a pick instead.
+fn commit_step(
+    repo: &Repository,
+    step: &RebasePlanStep,
+    message: Option<&str>,
+    first_rewritten: &mut Option<String>,
+) -> Result<Option<String>, GitError> {
+    let mut index = repo.index()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+
+    let tip = repo.head().and_then(|head| head.peel_to_commit())?;
+    let original = repo.find_commit(Oid::from_str(&step.commit)?)?;
+    let committer = repo.signature()?;
+    let message = message.or(step.message.as_deref());
+
+    let action = match step.action.as_str() {
+        "squash" | "fixup" if first_rewritten.is_none() => {
+            println!(
+                "[Rust Rebase] No rewritten commit to {} {} into, picking it",
+                step.action, step.commit
+            );
+            "pick"
+        }
+        action => action,
+    };
+
+    let pause = match action {
+        "squash" | "fixup" => {
+            // Fold the changes into the previous commit, keeping its author
+            let combined = match (message, action) {
+                (Some(m), _) => m.to_string(),
+                (None, "squash") => format!(
+                    "{}\n\n{}",
+                    tip.message().unwrap_or("").trim_end(),
+                    original.message().unwrap_or("")
+                ),
+                (None, _) => tip.message().unwrap_or("").to_string(),
+            };
+            let parents: Vec<Commit> = tip.parents().collect();
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let author = tip.author();
+            let oid = repo.commit(None, &author, &committer, &combined, &tree, &parent_refs)?;
+            repo.set_head_detached(oid)?;
+            None
+        }
+        action => {
+            if tree_id == tip.tree_id() {
+                // Nothing to pause on either, an edit or reword would amend another commit
+                println!("[Rust Rebase] Skipping empty commit {}", step.commit);
+                None
+            } else {
+                let author = original.author();
+                let oid = repo.commit(
+                    Some("HEAD"),
+                    &author,
+                    &committer,
+                    message.unwrap_or(original.message().unwrap_or("")),
+                    &tree,
+                    &[&tip],
+                )?;
+                first_rewritten.get_or_insert_with(|| oid.to_string());
+
+                match action {
+                    "edit" => Some("edit".to_string()),
+                    "reword" if message.is_none() => Some("reword".to_string()),
+                    _ => None,
+                }
+            }
+        }
+    };
+
+    repo.cleanup_state()?;
+    Ok(pause)
+}
+
+fn interactive_continue(
+    repo: &Repository,
+    mut state: InteractiveRebaseState,
+    message: Option<&str>,
+) -> Result<RebaseStatus, GitError> {
+    match state.stopped.as_deref() {
+        Some("conflicts") => {
+            let index = repo.index()?;
+            if index.has_conflicts() {
+                return Err(GitError::new(
+                    ErrorKind::Conflicts,
+                    "Cannot continue: resolve and stage all conflicted files first",
+                ));
+            }
+
+            let step = state.steps[state.current].clone();
+            let pause = commit_step(repo, &step, message, &mut state.first_rewritten)?;
+            state.current += 1;
+            state.stopped = None;
+            if let Some(pause) = pause {
+                state.stopped = Some(pause);
+                save_interactive_state(repo, &state)?;
+                return interactive_status(repo, &state);
+            }
+        }
+        Some("edit") | Some("reword") => {
+            // Amend the paused commit with staged changes and/or the new message
+            let mut index = repo.index()?;
+            let tree_id = index.write_tree()?;
+            let tip = repo.head().and_then(|head| head.peel_to_commit())?;
+
+            if message.is_some() || tree_id != tip.tree_id() {
+                let tree = repo.find_tree(tree_id)?;
+                tip.amend(Some("HEAD"), None, None, None, message, Some(&tree))?;
+            }
+            state.stopped = None;
+        }
+        _ => {}
+    }
+
+    save_interactive_state(repo, &state)?;
+    run_interactive(repo, state)
+}
+
+fn interactive_skip(
+    repo: &Repository,
+    mut state: InteractiveRebaseState,
+) -> Result<RebaseStatus, GitError> {
+    if state.stopped.as_deref() == Some("conflicts") {
+        reset_hard_to_head(repo)?;
+        repo.cleanup_state()?;
+        state.current += 1;
+    }
+    state.stopped = None;
+
+    save_interactive_state(repo, &state)?;
+    run_interactive(repo, state)
+}
+
+fn interactive_abort(repo: &Repository, state: InteractiveRebaseState) -> Result<(), GitError> {
+    // The branch itself is only moved when the rebase finishes
+    repo.set_head(&state.head_name)?;
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+    repo.cleanup_state()?;
+    clear_interactive_state(repo)?;
+
+    println!(
+        "[Rust Rebase] Aborted interactive rebase of {}",
+        state.head_name
+    );
+    Ok(())
+}
+
+fn finish_interactive(
+    repo: &Repository,
+    state: &InteractiveRebaseState,
+) -> Result<RebaseStatus, GitError> {
+    let tip = repo.head().and_then(|head| head.peel_to_commit())?;
+
+    repo.reference(&state.head_name, tip.id(), true, "rebase -i (finish)")?;
+    repo.set_head(&state.head_name)?;
+    clear_interactive_state(repo)?;
+
+    println!(
+        "[Rust Rebase] Interactive rebase of {} completed",
+        state.head_name
+    );
+
+    Ok(RebaseStatus {
+        state: "completed".to_string(),
+        current_step: state.steps.len(),
+        total_steps: state.steps.len(),
+        current_commit: None,
+        current_message: None,
+        onto: Some(state.onto.clone()),
+        conflicted_files: Vec::new(),
+    })
+}
+
+fn interactive_status(
+    repo: &Repository,
+    state: &InteractiveRebaseState,
+) -> Result<RebaseStatus, GitError> {
+    // Conflicts stop before the step is committed; edit and reword stop after it
+    let step_index = match state.stopped.as_deref() {
+        Some("conflicts") => state.current,
+        _ => state.current.saturating_sub(1),
+    };
+    let step = state.steps.get(step_index);
+
+    let current_message = step
+        .and_then(|s| Oid::from_str(&s.commit).ok())
+        .and_then(|id| repo.find_commit(id).ok())
+        .and_then(|commit| commit.summary().map(|s| s.to_string()));
+
+    Ok(RebaseStatus {
+        state: state
+            .stopped
+            .clone()
+            .unwrap_or_else(|| "stopped".to_string()),
+        current_step: step_index + 1,
+        total_steps: state.steps.len(),
+        current_commit: step.map(|s| s.commit.clone()),
+        current_message,
+        onto: Some(state.onto.clone()),
+        conflicted_files: conflicted_paths(repo)?,
+    })
+}
+
+fn ensure_can_rebase(repo: &Repository) -> Result<(), GitError> {
+    if repo.state() != RepositoryState::Clean || interactive_state_path(repo).exists() {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Cannot rebase: repository is in the middle of another operation ({:?})",
+                repo.state()
+            ),
+        ));
+    }
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(GitError::new(
+            ErrorKind::InvalidInput,
+            "Cannot rebase: HEAD is detached",
+        ));
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    opts.include_ignored(false);
+    let dirty = repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .any(|entry| !entry.status().is_empty());
+    if dirty {
+        return Err(GitError::new(
+            ErrorKind::UncommittedChanges,
+            "Cannot rebase: you have uncommitted changes",
+        ));
+    }
+
+    Ok(())
+}
+
+fn reset_hard_to_head(repo: &Repository) -> Result<(), GitError> {
+    let head = repo.head().and_then(|head| head.peel_to_commit())?;
+    repo.reset(
+        head.as_object(),
+        git2::ResetType::Hard,
+        Some(git2::build::CheckoutBuilder::default().force()),
+    )
+    .map_err(GitError::from)
+}
+
+fn resolve_commit<'repo>(repo: &'repo Repository, spec: &str) -> Result<Commit<'repo>, GitError> {
+    repo.revparse_single(spec)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(GitError::from)
+}
+
+/// Commits reachable from HEAD but not from `base`, oldest first.
+fn commits_since(repo: &Repository, base: Oid) -> Result<Vec<Oid>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push_head()?;
+    revwalk.hide(base)?;
+
+    revwalk
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(GitError::from)
+}
+
+fn interactive_state_path(repo: &Repository) -> PathBuf {
+    repo.path().join(INTERACTIVE_STATE_DIR).join("state.json")
+}
+
+fn load_interactive_state(repo: &Repository) -> Result<Option<InteractiveRebaseState>, GitError> {
+    let state_path = interactive_state_path(repo);
+    if !state_path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&state_path)?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(GitError::from)
+}
+
+fn save_interactive_state(
+    repo: &Repository,
+    state: &InteractiveRebaseState,
+) -> Result<(), GitError> {
+    let state_path = interactive_state_path(repo);
+    if let Some(dir) = state_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let contents = serde_json::to_string_pretty(state)?;
+    std::fs::write(&state_path, contents).map_err(GitError::from)
+}
+
+fn clear_interactive_state(repo: &Repository) -> Result<(), GitError> {
+    let dir = repo.path().join(INTERACTIVE_STATE_DIR);
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::path::Path;
+
+    /// A repository with a base commit and `count` commits on top, each adding a file.
+    fn repo_with_commits(name: &str, count: usize) -> (Repository, Oid, Vec<Oid>) {
+        let dir =
+            std::env::temp_dir().join(format!("gitgud-rebase-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        let base = commit_file(&repo, "base.txt", "base");
+        let commits = (0..count)
+            .map(|i| commit_file(&repo, &format!("file{}.txt", i), &i.to_string()))
+            .collect();
+        (repo, base, commits)
+    }
+
+    fn commit_file(repo: &Repository, file: &str, contents: &str) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join(file), contents).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        commit_index(repo, file)
+    }
+
+    fn commit_index(repo: &Repository, message: &str) -> Oid {
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    fn step(action: &str, commit: Oid) -> RebasePlanStep {
+        RebasePlanStep {
+            action: action.to_string(),
+            commit: commit.to_string(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn validate_plan_accepts_picks_squashes_and_drops() {
+        let (repo, base, commits) = repo_with_commits("valid", 3);
+        let steps = [
+            step("pick", commits[0]),
+            step("squash", commits[1]),
+            step("fixup", commits[2]),
+        ];
+        assert!(validate_plan(&repo, base, &steps).is_ok());
+
+        // Dropping everything leaves the branch at the base, as with git
+        let dropped: Vec<_> = commits.iter().map(|&c| step("drop", c)).collect();
+        assert!(validate_plan(&repo, base, &dropped).is_ok());
+    }
+
+    #[test]
+    fn validate_plan_rejects_squash_without_previous_commit() {
+        let (repo, base, commits) = repo_with_commits("squash-first", 2);
+        for action in ["squash", "fixup"] {
+            let steps = [step(action, commits[0]), step("pick", commits[1])];
+            let error = validate_plan(&repo, base, &steps).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::InvalidInput);
+        }
+
+        // A dropped commit does not count as the previous one
+        let steps = [step("drop", commits[0]), step("squash", commits[1])];
+        assert!(validate_plan(&repo, base, &steps).is_err());
+    }
+
+    #[test]
+    fn validate_plan_rejects_unknown_and_duplicate_commits() {
+        let (repo, base, commits) = repo_with_commits("unknown", 2);
+
+        // The base itself is outside the range
+        let steps = [step("pick", base), step("pick", commits[0])];
+        assert!(validate_plan(&repo, base, &steps).is_err());
+
+        let missing = RebasePlanStep {
+            action: "pick".to_string(),
+            commit: "1234567890123456789012345678901234567890".to_string(),
+            message: None,
+        };
+        assert!(validate_plan(&repo, base, &[missing]).is_err());
+
+        let garbage = RebasePlanStep {
+            action: "pick".to_string(),
+            commit: "not a commit".to_string(),
+            message: None,
+        };
+        assert!(validate_plan(&repo, base, &[garbage]).is_err());
+
+        let steps = [step("pick", commits[0]), step("pick", commits[0])];
+        assert!(validate_plan(&repo, base, &steps).is_err());
+
+        let steps = [step("reorder", commits[0])];
+        assert!(validate_plan(&repo, base, &steps).is_err());
+    }
+
+    #[test]
+    fn squash_after_skipped_empty_commit_is_picked() {
+        let (repo, base, _) = repo_with_commits("squash-empty", 0);
+        let empty = commit_index(&repo, "empty");
+        let change = commit_file(&repo, "change.txt", "change");
+        let path = repo.workdir().unwrap().to_string_lossy().to_string();
+
+        let steps = vec![step("pick", empty), step("squash", change)];
+        let status = interactive_rebase(&path, &base.to_string(), steps).unwrap();
+        assert_eq!(status.state, "completed");
+
+        // The change became a commit of its own instead of being folded into the base
+        let tip = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(tip.parent_id(0).unwrap(), base);
+        assert_eq!(tip.message(), Some("change.txt"));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::discovery::open_repository;
+use crate::error::{ErrorKind, GitError};
+use crate::progress::{remote_callbacks, ProgressReporter};
+use git2::{BranchType, Direction, Remote};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: Option<String>,
+    pub push_url: Option<String>, // only set when it differs from `url`
+    pub fetch_refspecs: Vec<String>,
+    pub push_refspecs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteHeadInfo {
+    pub name: String,
+    pub id: String,
+    pub tracking_ref: Option<String>,
+    pub status: String, // "tracked", "new"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteDetails {
+    pub remote: RemoteInfo,
+    pub default_branch: Option<String>,
+    pub heads: Vec<RemoteHeadInfo>,
+    pub stale: Vec<String>, // remote-tracking branches the remote no longer has
+}
+
+pub fn list_remotes(path: &str) -> Result<Vec<RemoteInfo>, GitError> {
+    let repo = open_repository(path)?;
+    let names = repo.remotes()?;
+    let mut remotes = Vec::new();
+    for name in names.iter().flatten() {
+        remotes.push(remote_info(&repo.find_remote(name)?)?);
+    }
+    Ok(remotes)
+}
+
+pub fn add_remote(path: &str, name: &str, url: &str) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    repo.remote(name, url)?;
+    Ok(())
+}
+
+/// Renames a remote along with its remote-tracking branches. Returns the fetch refspecs
+/// that are not the default one and were left for the user to update.
+pub fn rename_remote(path: &str, name: &str, new_name: &str) -> Result<Vec<String>, GitError> {
+    let repo = open_repository(path)?;
+    let problems = repo.remote_rename(name, new_name)?;
+    println!("[Rust Remotes] Renamed remote {} to {}", name, new_name);
+    Ok(problems.iter().flatten().map(str::to_string).collect())
+}
+
+/// Removes a remote, its remote-tracking branches and the configuration that refers to it.
+pub fn remove_remote(path: &str, name: &str) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    repo.remote_delete(name)?;
+    println!("[Rust Remotes] Removed remote {}", name);
+    Ok(())
+}
+
+pub fn set_remote_url(path: &str, name: &str, url: &str) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    repo.find_remote(name)?;
+    repo.remote_set_url(name, url)?;
+    Ok(())
+}
+
+/// Sets the URL pushes go to, e.g. a fork while fetching from upstream.
+/// None removes it so pushes use the fetch URL again.
+pub fn set_remote_push_url(path: &str, name: &str, push_url: Option<&str>) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    repo.find_remote(name)?;
+    match push_url {
+        Some(push_url) => repo.remote_set_pushurl(name, Some(push_url))?,
+        None => {
+            // remote_set_pushurl(None) fails when the key is already absent
+            let mut config = repo.config()?;
+            let key = format!("remote.{}.pushurl", name);
+            if config.get_entry(&key).is_ok() {
+                config.remove(&key)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Connects to a remote and lists its branches, like `git remote show`.
+pub fn show_remote(
+    path: &str,
+    name: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<RemoteDetails, GitError> {
+    let repo = open_repository(path)?;
+    let mut remote = repo.find_remote(name)?;
+    let info = remote_info(&remote)?;
+
+    println!("[Rust Remotes] Listing heads of {}", name);
+    let (advertised, default_branch) = {
+        let url = remote.url().map(str::to_string);
+        let connection = remote.connect_auth(
+            Direction::Fetch,
+            Some(remote_callbacks(progress, url.as_deref())),
+            None,
+        )?;
+        let advertised: Vec<(String, String)> = connection
+            .list()?
+            .iter()
+            .filter(|head| head.name().starts_with("refs/heads/"))
+            .map(|head| (head.name().to_string(), head.oid().to_string()))
+            .collect();
+        let default_branch = connection
+            .default_branch()
+            .ok()
+            .and_then(|name| name.as_str().map(str::to_string));
+        (advertised, default_branch)
+    };
+
+    let mut heads = Vec::new();
+    let mut tracking_refs = Vec::new();
+    for (refname, id) in advertised {
+        let tracking = tracking_ref(&remote, &refname);
+        let tracked = tracking
+            .as_deref()
+            .is_some_and(|tracking| repo.find_reference(tracking).is_ok());
+        if let Some(tracking) = &tracking {
+            tracking_refs.push(tracking.clone());
+        }
+        heads.push(RemoteHeadInfo {
+            name: refname
+                .strip_prefix("refs/heads/")
+                .unwrap_or(&refname)
+                .to_string(),
+            id,
+            tracking_ref: tracking,
+            status: if tracked { "tracked" } else { "new" }.to_string(),
+        });
+    }
+
+    let prefix = format!("{}/", name);
+    let mut stale = Vec::new();
+    for branch in repo.branches(Some(BranchType::Remote))? {
+        let (branch, _) = branch?;
+        let reference = branch.get();
+        let (Some(refname), Some(short)) = (reference.name(), reference.shorthand()) else {
+            continue;
+        };
+        if short.starts_with(&prefix)
+            && reference.symbolic_target().is_none()
+            && !tracking_refs.iter().any(|tracking| tracking == refname)
+        {
+            stale.push(short.to_string());
+        }
+    }
+
+    Ok(RemoteDetails {
+        remote: info,
+        default_branch: default_branch.map(|name| {
+            name.strip_prefix("refs/heads/")
+                .unwrap_or(&name)
+                .to_string()
+        }),
+        heads,
+        stale,
+    })
+}
+
+/// The remote-tracking ref a remote branch is fetched into, from the remote's fetch refspecs.
+pub fn tracking_ref(remote: &Remote<'_>, remote_ref: &str) -> Option<String> {
+    remote
+        .refspecs()
+        .filter(|spec| spec.direction() == Direction::Fetch)
+        .find(|spec| spec.src_matches(remote_ref))
+        .and_then(|spec| spec.transform(remote_ref).ok())
+        .and_then(|name| name.as_str().map(str::to_string))
+}
+
+fn remote_info(remote: &Remote<'_>) -> Result<RemoteInfo, GitError> {
+    let name = remote
+        .name()
+        .ok_or_else(|| GitError::new(ErrorKind::InvalidInput, "Remote name is not valid UTF-8"))?;
+    let url = remote.url().map(str::to_string);
+    let push_url = remote
+        .pushurl()
+        .map(str::to_string)
+        .filter(|push_url| Some(push_url) != url.as_ref());
+    let specs = |direction: Direction| -> Vec<String> {
+        remote
+            .refspecs()
+            .filter(|spec| spec.direction() == direction)
+            .filter_map(|spec| spec.str().map(str::to_string))
+            .collect()
+    };
+
+    Ok(RemoteInfo {
+        name: name.to_string(),
+        url,
+        push_url,
+        fetch_refspecs: specs(Direction::Fetch),
+        push_refspecs: specs(Direction::Push),
+    })
+}
@@ -0,0 +1,93 @@
+use crate::discovery::open_repository;
+use crate::error::GitError;
+use git2::Repository;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Repositories kept open beyond this count are closed, least recently used first.
+const MAX_CACHED_REPOSITORIES: usize = 32;
+
+struct CachedRepo {
+    repo: Arc<Mutex<Repository>>,
+    git_dir: PathBuf,
+    last_used: Instant,
+}
+
+/// Opened repositories shared between commands, so the info/status/commits/branches
+/// refresh of the sidebar does not reopen every repository each time.
+#[derive(Clone, Default)]
+pub struct RepoCache {
+    repos: Arc<Mutex<HashMap<String, CachedRepo>>>,
+}
+
+impl RepoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` with the cached repository for `path`, opening it on first use.
+    pub fn with_repo<T, F>(&self, path: &str, f: F) -> Result<T, GitError>
+    where
+        F: FnOnce(&Repository) -> Result<T, GitError>,
+    {
+        let repo = self.get_or_open(path)?;
+        let repo = match repo.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        f(&repo)
+    }
+
+    /// Drops the cached handle of a repository, e.g. after it was removed or re-created.
+    pub fn invalidate(&self, path: &str) {
+        if self.lock().remove(path).is_some() {
+            println!("[Rust Cache] Invalidated {}", path);
+        }
+    }
+
+    fn get_or_open(&self, path: &str) -> Result<Arc<Mutex<Repository>>, GitError> {
+        let mut repos = self.lock();
+
+        if let Some(cached) = repos.get_mut(path) {
+            // The repository may have been deleted or moved since it was opened
+            if cached.git_dir.exists() {
+                cached.last_used = Instant::now();
+                return Ok(cached.repo.clone());
+            }
+            repos.remove(path);
+        }
+
+        let opened = open_repository(path)?;
+        let git_dir = opened.path().to_path_buf();
+        let repo = Arc::new(Mutex::new(opened));
+
+        if repos.len() >= MAX_CACHED_REPOSITORIES {
+            let oldest = repos
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                repos.remove(&oldest);
+            }
+        }
+
+        repos.insert(
+            path.to_string(),
+            CachedRepo {
+                repo: repo.clone(),
+                git_dir,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(repo)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CachedRepo>> {
+        match self.repos.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::discovery::open_repository;
+use crate::error::{ErrorKind, GitError};
+use crate::git_operations::CommitInfo;
+use crate::progress::{checkout_builder, remote_callbacks, ProgressReporter};
+use git2::{FetchOptions, Oid, Repository, Submodule, SubmoduleIgnore, SubmoduleStatus};
+use serde::{Deserialize, Serialize};
+
+/// Commits listed on each side of a pointer change, a newly added submodule would
+/// otherwise list its whole history.
+const MAX_RANGE_COMMITS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubmoduleInfo {
+    pub name: String,
+    pub path: String,
+    pub url: Option<String>,
+    pub branch: Option<String>,
+    pub recorded_id: Option<String>, // commit recorded in HEAD
+    pub staged_id: Option<String>,   // commit recorded in the index
+    pub checked_out_id: Option<String>,
+    pub state: String, // "uninitialized", "current", "moved"
+    pub dirty: bool,   // the submodule's own working tree has changes
+}
+
+/// A submodule pointer change shown instead of a text diff: the commits the new
+/// pointer adds and, when it moved backwards or sideways, the ones it drops.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubmoduleChange {
+    pub path: String,
+    pub old_id: Option<String>,
+    pub new_id: Option<String>,
+    pub added: Vec<CommitInfo>,
+    pub removed: Vec<CommitInfo>,
+    pub dirty: bool,
+    pub complete: bool, // false when the submodule is not checked out or lacks the commits
+}
+
+pub fn list_submodules(path: &str) -> Result<Vec<SubmoduleInfo>, GitError> {
+    let repo = open_repository(path)?;
+    let mut submodules = Vec::new();
+    for submodule in repo.submodules()? {
+        let name = submodule.name().unwrap_or_default().to_string();
+        let status = repo.submodule_status(&name, SubmoduleIgnore::None)?;
+        let checked_out = submodule.workdir_id();
+        let state = if status.contains(SubmoduleStatus::WD_UNINITIALIZED) || checked_out.is_none() {
+            "uninitialized"
+        } else if checked_out != submodule.index_id() {
+            "moved"
+        } else {
+            "current"
+        };
+
+        submodules.push(SubmoduleInfo {
+            name,
+            path: submodule.path().to_string_lossy().to_string(),
+            url: submodule.url().map(str::to_string),
+            branch: submodule.branch().map(str::to_string),
+            recorded_id: submodule.head_id().map(|id| id.to_string()),
+            staged_id: submodule.index_id().map(|id| id.to_string()),
+            checked_out_id: checked_out.map(|id| id.to_string()),
+            state: state.to_string(),
+            dirty: is_dirty(status),
+        });
+    }
+    Ok(submodules)
+}
+
+/// Copies the URLs of the submodules from `.gitmodules` into the repository config, like
+/// `git submodule init`. Every submodule is initialized when `paths` is None.
+pub fn init_submodules(path: &str, paths: Option<&[String]>) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    for mut submodule in select(&repo, paths)? {
+        submodule.init(false)?;
+        println!(
+            "[Rust Submodules] Initialized {}",
+            submodule.path().display()
+        );
+    }
+    Ok(())
+}
+
+/// Applies URL changes of `.gitmodules` to the repository config and to the remotes of
+/// the checked-out submodules, like `git submodule sync`.
+pub fn sync_submodules(path: &str, paths: Option<&[String]>) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    for mut submodule in select(&repo, paths)? {
+        submodule.sync()?;
+        println!("[Rust Submodules] Synced {}", submodule.path().display());
+    }
+    Ok(())
+}
+
+/// Checks out the commits the repository records for its submodules, fetching them when
+/// needed, like `git submodule update [--init] [--recursive]`.
+pub fn update_submodules(
+    path: &str,
+    paths: Option<&[String]>,
+    init: bool,
+    recursive: bool,
+    progress: Option<&ProgressReporter>,
+) -> Result<(), GitError> {
+    let repo = open_repository(path)?;
+    update_repo_submodules(&repo, paths, init, recursive, progress)
+}
+
+pub fn update_repo_submodules(
+    repo: &Repository,
+    paths: Option<&[String]>,
+    init: bool,
+    recursive: bool,
+    progress: Option<&ProgressReporter>,
+) -> Result<(), GitError> {
+    for mut submodule in select(repo, paths)? {
+        if progress.is_some_and(|p| p.is_cancelled()) {
+            return Err(GitError::cancelled());
+        }
+        println!("[Rust Submodules] Updating {}", submodule.path().display());
+
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(remote_callbacks(progress, submodule.url()));
+        let mut update_options = git2::SubmoduleUpdateOptions::new();
+        update_options
+            .fetch(fetch_options)
+            .checkout(checkout_builder(progress));
+        submodule.update(init, Some(&mut update_options))?;
+
+        if recursive {
+            let nested = submodule.open()?;
+            update_repo_submodules(&nested, None, init, recursive, progress)?;
+        }
+    }
+    Ok(())
+}
+
+/// The pointer change of `file_path` if it is a submodule: HEAD against the index when
+/// `staged`, otherwise the index against the commit checked out in the submodule.
+pub fn submodule_change(
+    repo: &Repository,
+    file_path: &str,
+    staged: bool,
+) -> Result<Option<SubmoduleChange>, GitError> {
+    let Ok(submodule) = repo.find_submodule(file_path) else {
+        return Ok(None);
+    };
+    let (old_id, new_id, dirty) = if staged {
+        (submodule.head_id(), submodule.index_id(), false)
+    } else {
+        let name = submodule.name().unwrap_or(file_path);
+        let status = repo.submodule_status(name, SubmoduleIgnore::None)?;
+        (
+            submodule.index_id(),
+            submodule.workdir_id(),
+            is_dirty(status),
+        )
+    };
+
+    let range = submodule
+        .open()
+        .ok()
+        .and_then(|sub| commit_range(&sub, old_id, new_id).ok());
+    let complete = range.is_some();
+    let (added, removed) = range.unwrap_or_default();
+
+    Ok(Some(SubmoduleChange {
+        path: file_path.to_string(),
+        old_id: old_id.map(|id| id.to_string()),
+        new_id: new_id.map(|id| id.to_string()),
+        added,
+        removed,
+        dirty,
+        complete,
+    }))
+}
+
+/// The commits reachable from `new` but not `old`, and the other way around.
+fn commit_range(
+    repo: &Repository,
+    old: Option<Oid>,
+    new: Option<Oid>,
+) -> Result<(Vec<CommitInfo>, Vec<CommitInfo>), git2::Error> {
+    let walk = |from: Option<Oid>, hide: Option<Oid>| -> Result<Vec<CommitInfo>, git2::Error> {
+        let Some(from) = from else {
+            return Ok(Vec::new());
+        };
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(from)?;
+        if let Some(hide) = hide {
+            revwalk.hide(hide)?;
+        }
+        let mut commits = Vec::new();
+        for oid in revwalk.take(MAX_RANGE_COMMITS) {
+            let commit = repo.find_commit(oid?)?;
+            commits.push(CommitInfo {
+                id: commit.id().to_string(),
+                short_id: format!("{:.7}", commit.id()),
+                message: commit.message().unwrap_or("").to_string(),
+                author: commit.author().name().unwrap_or("Unknown").to_string(),
+                timestamp: commit.time().seconds(),
+            });
+        }
+        Ok(commits)
+    };
+    Ok((walk(new, old)?, walk(old, new)?))
+}
+
+fn is_dirty(status: SubmoduleStatus) -> bool {
+    status.intersects(
+        SubmoduleStatus::WD_INDEX_MODIFIED
+            | SubmoduleStatus::WD_WD_MODIFIED
+            | SubmoduleStatus::WD_UNTRACKED,
+    )
+}
+
+/// The submodules at `paths`, or all of them.
+fn select<'r>(
+    repo: &'r Repository,
+    paths: Option<&[String]>,
+) -> Result<Vec<Submodule<'r>>, GitError> {
+    let submodules = repo.submodules()?;
+    let Some(paths) = paths else {
+        return Ok(submodules);
+    };
+    if let Some(unknown) = paths.iter().find(|path| {
+        !submodules
+            .iter()
+            .any(|s| s.path().to_string_lossy() == path.as_str())
+    }) {
+        return Err(GitError::new(
+            ErrorKind::NotFound,
+            format!("{} is not a submodule", unknown),
+        ));
+    }
+    Ok(submodules
+        .into_iter()
+        .filter(|s| {
+            paths
+                .iter()
+                .any(|path| s.path().to_string_lossy() == path.as_str())
+        })
+        .collect())
+}
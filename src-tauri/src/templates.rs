@@ -0,0 +1,95 @@
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+
+/// .gitignore templates bundled with the app, by name.
+const GITIGNORE_TEMPLATES: [(&str, &str); 8] = [
+    (
+        "Node",
+        include_str!("../templates/gitignore/Node.gitignore"),
+    ),
+    (
+        "Python",
+        include_str!("../templates/gitignore/Python.gitignore"),
+    ),
+    (
+        "Rust",
+        include_str!("../templates/gitignore/Rust.gitignore"),
+    ),
+    ("Go", include_str!("../templates/gitignore/Go.gitignore")),
+    (
+        "Java",
+        include_str!("../templates/gitignore/Java.gitignore"),
+    ),
+    ("C++", include_str!("../templates/gitignore/C++.gitignore")),
+    (
+        "macOS",
+        include_str!("../templates/gitignore/macOS.gitignore"),
+    ),
+    (
+        "Windows",
+        include_str!("../templates/gitignore/Windows.gitignore"),
+    ),
+];
+
+/// License texts bundled with the app, by SPDX identifier.
+/// `{year}` and `{holder}` are filled in when the file is written.
+const LICENSE_TEMPLATES: [(&str, &str); 4] = [
+    ("MIT", include_str!("../templates/licenses/MIT.txt")),
+    ("ISC", include_str!("../templates/licenses/ISC.txt")),
+    (
+        "BSD-2-Clause",
+        include_str!("../templates/licenses/BSD-2-Clause.txt"),
+    ),
+    (
+        "Unlicense",
+        include_str!("../templates/licenses/Unlicense.txt"),
+    ),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InitTemplates {
+    pub gitignore: Vec<String>,
+    pub licenses: Vec<String>,
+}
+
+pub fn init_templates() -> InitTemplates {
+    InitTemplates {
+        gitignore: GITIGNORE_TEMPLATES
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        licenses: LICENSE_TEMPLATES
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect(),
+    }
+}
+
+/// Concatenates the named .gitignore templates, each under a header naming it.
+/// Returns the first unknown name as the error.
+pub fn gitignore_for(names: &[String]) -> Result<String, String> {
+    let mut contents = String::new();
+    for name in names {
+        let (name, template) = GITIGNORE_TEMPLATES
+            .iter()
+            .find(|(template, _)| template.eq_ignore_ascii_case(name))
+            .ok_or_else(|| name.clone())?;
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        contents.push_str(&format!("### {} ###\n{}", name, template));
+    }
+    Ok(contents)
+}
+
+/// The text of a license for the current year, or None for an unknown identifier.
+pub fn license_for(id: &str, holder: &str) -> Option<String> {
+    let (_, template) = LICENSE_TEMPLATES
+        .iter()
+        .find(|(license, _)| license.eq_ignore_ascii_case(id))?;
+    Some(
+        template
+            .replace("{year}", &chrono::Local::now().year().to_string())
+            .replace("{holder}", holder),
+    )
+}
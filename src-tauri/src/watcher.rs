@@ -0,0 +1,243 @@
+use crate::discovery::open_repository;
+use crate::error::GitError;
+use git2::Repository;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Changes arriving within this delay of each other are reported as a single event,
+/// a checkout or a build touches many files at once.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Upper bound on how long a burst of changes is gathered before it is reported anyway.
+const MAX_DEBOUNCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepoChangedEvent {
+    pub path: String,
+    pub kinds: Vec<String>, // "worktree", "index", "head", "refs"
+}
+
+type SharedWatcher = Arc<Mutex<RecommendedWatcher>>;
+
+/// Filesystem watchers of the open repositories, keyed by the path the frontend uses.
+/// Dropping a watcher stops it, along with its debounce thread.
+#[derive(Clone, Default)]
+pub struct RepoWatchers {
+    watchers: Arc<Mutex<HashMap<String, SharedWatcher>>>,
+}
+
+impl RepoWatchers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts watching the working tree and git directory of `path`, calling `on_change`
+    /// with the kinds of change seen. Watching an already watched repository restarts it.
+    /// Folders of the working tree are watched one by one, leaving out ignored ones like
+    /// `node_modules` or `target`, and folders created later are added as they appear.
+    pub fn watch<F>(&self, path: &str, on_change: F) -> Result<(), GitError>
+    where
+        F: Fn(&RepoChangedEvent) + Send + 'static,
+    {
+        let root = std::fs::canonicalize(path)?;
+        let repo = open_repository(&root.to_string_lossy())?;
+        let git_dir = repo.path().to_path_buf();
+        let workdir = repo.workdir().map(Path::to_path_buf);
+
+        // Each changed path, with whether it may be a folder that just appeared
+        let (sender, receiver) = mpsc::channel::<(PathBuf, bool)>();
+        let repo_path = path.to_string();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            match result {
+                // Reads (our own status refreshes included) must not count as changes
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(event) => {
+                    let appeared = matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                    );
+                    for path in event.paths {
+                        let _ = sender.send((path, appeared));
+                    }
+                }
+                Err(e) => println!("[Rust Watcher] Error watching {}: {}", repo_path, e),
+            }
+        })?;
+
+        watcher.watch(&git_dir, RecursiveMode::Recursive)?;
+        if let Some(workdir) = &workdir {
+            watch_tree(&mut watcher, &repo, &git_dir, workdir, workdir)?;
+        }
+
+        let watcher = Arc::new(Mutex::new(watcher));
+        // Weak, the thread must not keep the watcher, and so itself, alive
+        let thread_watcher = Arc::downgrade(&watcher);
+        let repo_path = path.to_string();
+        thread::spawn(move || {
+            // Ends once the watcher, and with it the sender, has been dropped
+            while let Ok(first) = receiver.recv() {
+                let started = Instant::now();
+                let mut paths = vec![first];
+                while started.elapsed() < MAX_DEBOUNCE {
+                    match receiver.recv_timeout(DEBOUNCE_INTERVAL) {
+                        Ok(path) => paths.push(path),
+                        Err(_) => break,
+                    }
+                }
+
+                if let Some(workdir) = &workdir {
+                    for (path, _) in paths.iter().filter(|(_, appeared)| *appeared) {
+                        watch_new_folder(&thread_watcher, &repo, &git_dir, workdir, path);
+                    }
+                }
+
+                let mut kinds: Vec<String> = Vec::new();
+                for (path, _) in &paths {
+                    if let Some(kind) = classify_change(&repo, &git_dir, workdir.as_deref(), path) {
+                        if !kinds.iter().any(|k| k == kind) {
+                            kinds.push(kind.to_string());
+                        }
+                    }
+                }
+
+                if !kinds.is_empty() {
+                    on_change(&RepoChangedEvent {
+                        path: repo_path.clone(),
+                        kinds,
+                    });
+                }
+            }
+        });
+
+        self.lock().insert(path.to_string(), watcher);
+        println!("[Rust Watcher] Watching {}", path);
+        Ok(())
+    }
+
+    /// Stops watching a repository. Returns false if it was not watched.
+    pub fn unwatch(&self, path: &str) -> bool {
+        let removed = self.lock().remove(path).is_some();
+        if removed {
+            println!("[Rust Watcher] Stopped watching {}", path);
+        }
+        removed
+    }
+
+    pub fn watched(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, SharedWatcher>> {
+        match self.watchers.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Watches `dir` and the folders below it that are neither ignored nor the git directory,
+/// each on its own. Symlinks are not followed, they can loop back up the tree.
+fn watch_tree(
+    watcher: &mut RecommendedWatcher,
+    repo: &Repository,
+    git_dir: &Path,
+    workdir: &Path,
+    dir: &Path,
+) -> notify::Result<()> {
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !entry.file_type().is_ok_and(|t| t.is_dir())
+                || is_skipped(repo, git_dir, workdir, &path)
+            {
+                continue;
+            }
+            // A folder removed in the meantime is simply not watched
+            match watcher.watch(&path, RecursiveMode::NonRecursive) {
+                Ok(()) => pending.push(path),
+                Err(e) => println!("[Rust Watcher] Cannot watch {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds watches for a folder created or moved into the working tree after watching started.
+fn watch_new_folder(
+    watcher: &Weak<Mutex<RecommendedWatcher>>,
+    repo: &Repository,
+    git_dir: &Path,
+    workdir: &Path,
+    path: &Path,
+) {
+    let is_dir = std::fs::symlink_metadata(path).is_ok_and(|m| m.is_dir());
+    if !is_dir || !path.starts_with(workdir) || is_skipped(repo, git_dir, workdir, path) {
+        return;
+    }
+    let Some(watcher) = watcher.upgrade() else {
+        return;
+    };
+    let mut watcher = match watcher.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Err(e) = watch_tree(&mut watcher, repo, git_dir, workdir, path) {
+        println!("[Rust Watcher] Cannot watch {}: {}", path.display(), e);
+    }
+}
+
+/// Whether a folder of the working tree is left unwatched: the git directory, watched on
+/// its own, and folders the repository ignores.
+fn is_skipped(repo: &Repository, git_dir: &Path, workdir: &Path, dir: &Path) -> bool {
+    if dir.starts_with(git_dir) {
+        return true;
+    }
+    match dir.strip_prefix(workdir) {
+        Ok(relative) => repo.is_path_ignored(relative).unwrap_or(false),
+        Err(_) => true,
+    }
+}
+
+/// Maps a changed path to the kind of change the frontend refreshes for,
+/// or None for paths it does not care about (objects, logs, lock files, ignored files).
+fn classify_change(
+    repo: &Repository,
+    git_dir: &Path,
+    workdir: Option<&Path>,
+    path: &Path,
+) -> Option<&'static str> {
+    if let Ok(relative) = path.strip_prefix(git_dir) {
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if relative.ends_with(".lock") {
+            return None;
+        }
+        return match relative.as_str() {
+            "index" => Some("index"),
+            "HEAD" | "ORIG_HEAD" | "MERGE_HEAD" => Some("head"),
+            "packed-refs" => Some("refs"),
+            r if r.starts_with("refs/") => Some("refs"),
+            _ => None,
+        };
+    }
+
+    let relative = path.strip_prefix(workdir?).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    match repo.is_path_ignored(relative) {
+        Ok(true) => None,
+        _ => Some("worktree"),
+    }
+}
@@ -0,0 +1,253 @@
+use crate::autofetch::AutoFetchSettings;
+use crate::error::{ErrorKind, GitError};
+use git2::{ErrorCode, Repository};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RepoSettings {
+    pub auto_fetch: bool,
+    pub default_remote: Option<String>,
+}
+
+impl Default for RepoSettings {
+    fn default() -> Self {
+        RepoSettings {
+            auto_fetch: true,
+            default_remote: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WorkspaceRepo {
+    pub path: String,
+    pub alias: Option<String>,
+    pub group: Option<String>,
+    pub last_opened: Option<i64>, // unix seconds
+    pub settings: RepoSettings,
+    pub missing: bool, // the path no longer holds a repository, checked on load
+}
+
+/// The repositories shown in the sidebar, in display order, and the groups they are sorted into.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Workspace {
+    pub groups: Vec<String>,
+    pub repositories: Vec<WorkspaceRepo>,
+    pub auto_fetch: AutoFetchSettings,
+}
+
+impl Workspace {
+    fn find_mut(&mut self, path: &str) -> Result<&mut WorkspaceRepo, GitError> {
+        self.repositories
+            .iter_mut()
+            .find(|repo| repo.path == path)
+            .ok_or_else(|| {
+                GitError::new(
+                    ErrorKind::NotFound,
+                    format!("{} is not in the workspace", path),
+                )
+            })
+    }
+
+    /// Flags repositories whose folder was moved or deleted since they were added.
+    pub fn validate(&mut self) {
+        for repo in &mut self.repositories {
+            repo.missing = !is_repository(&repo.path);
+            if repo.missing {
+                println!("[Rust Workspace] Repository missing: {}", repo.path);
+            }
+        }
+    }
+
+    pub fn add(&mut self, paths: &[String]) {
+        for path in paths {
+            if !self.repositories.iter().any(|repo| &repo.path == path) {
+                self.repositories.push(WorkspaceRepo {
+                    path: path.clone(),
+                    missing: !is_repository(path),
+                    ..WorkspaceRepo::default()
+                });
+            }
+        }
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        self.repositories.retain(|repo| repo.path != path);
+    }
+
+    /// Puts the repositories in the order of `paths`. Repositories not listed keep their
+    /// relative order after the listed ones.
+    pub fn reorder(&mut self, paths: &[String]) {
+        let position = |repo: &WorkspaceRepo| {
+            paths
+                .iter()
+                .position(|path| path == &repo.path)
+                .unwrap_or(paths.len())
+        };
+        self.repositories.sort_by_key(position);
+    }
+
+    pub fn update(
+        &mut self,
+        path: &str,
+        alias: Option<String>,
+        group: Option<String>,
+        settings: RepoSettings,
+    ) -> Result<(), GitError> {
+        let alias = alias.filter(|alias| !alias.trim().is_empty());
+        let group = group.filter(|group| !group.trim().is_empty());
+        if let Some(group) = &group {
+            if !self.groups.contains(group) {
+                self.groups.push(group.clone());
+            }
+        }
+        let repo = self.find_mut(path)?;
+        repo.alias = alias;
+        repo.group = group;
+        repo.settings = settings;
+        Ok(())
+    }
+
+    pub fn mark_opened(&mut self, path: &str) -> Result<(), GitError> {
+        self.find_mut(path)?.last_opened = Some(chrono::Utc::now().timestamp());
+        Ok(())
+    }
+
+    /// Replaces the list of groups, which also sets their order. Repositories in a group
+    /// that is no longer listed become ungrouped.
+    pub fn set_groups(&mut self, groups: Vec<String>) {
+        let mut unique: Vec<String> = Vec::new();
+        for group in groups {
+            if !group.trim().is_empty() && !unique.contains(&group) {
+                unique.push(group);
+            }
+        }
+        for repo in &mut self.repositories {
+            if repo
+                .group
+                .as_ref()
+                .is_some_and(|group| !unique.contains(group))
+            {
+                repo.group = None;
+            }
+        }
+        self.groups = unique;
+    }
+
+    pub fn rename_group(&mut self, name: &str, new_name: &str) -> Result<(), GitError> {
+        if new_name.trim().is_empty() {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                "Group name cannot be empty",
+            ));
+        }
+        if name != new_name && self.groups.iter().any(|group| group == new_name) {
+            return Err(GitError::new(
+                ErrorKind::InvalidInput,
+                format!("A group named {} already exists", new_name),
+            ));
+        }
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|group| *group == name)
+            .ok_or_else(|| {
+                GitError::new(ErrorKind::NotFound, format!("No group named {}", name))
+            })?;
+        *group = new_name.to_string();
+        for repo in &mut self.repositories {
+            if repo.group.as_deref() == Some(name) {
+                repo.group = Some(new_name.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `path` itself is a repository, without looking in the folders above it: a
+/// deleted repository inside another one's working tree is missing. A repository git
+/// refuses to open because of its owner is still there.
+fn is_repository(path: &str) -> bool {
+    match Repository::open(path) {
+        Ok(_) => true,
+        Err(e) => e.code() == ErrorCode::Owner,
+    }
+}
+
+/// The workspace, persisted as JSON in the app config directory.
+#[derive(Clone, Default)]
+pub struct WorkspaceStore {
+    file: Option<PathBuf>,
+    workspace: Arc<Mutex<Workspace>>,
+}
+
+impl WorkspaceStore {
+    /// Reads the saved workspace and checks that its repositories still exist. A file that
+    /// cannot be read is moved aside to `<name>.bak` rather than overwritten by the next save.
+    pub fn load(file: Option<PathBuf>) -> Self {
+        let mut workspace = match &file {
+            Some(file) if file.exists() => Self::read(file).unwrap_or_else(|e| {
+                let backup = file.with_extension("json.bak");
+                println!(
+                    "[Rust Workspace] Could not read {}: {}, moving it to {}",
+                    file.display(),
+                    e,
+                    backup.display()
+                );
+                if let Err(e) = std::fs::rename(file, &backup) {
+                    println!("[Rust Workspace] Could not move {}: {}", file.display(), e);
+                }
+                Workspace::default()
+            }),
+            _ => Workspace::default(),
+        };
+        workspace.validate();
+        println!(
+            "[Rust Workspace] Loaded {} repositories",
+            workspace.repositories.len()
+        );
+        WorkspaceStore {
+            file,
+            workspace: Arc::new(Mutex::new(workspace)),
+        }
+    }
+
+    fn read(file: &Path) -> Result<Workspace, GitError> {
+        let contents = std::fs::read_to_string(file)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn get(&self) -> Workspace {
+        self.lock().clone()
+    }
+
+    /// Applies `change` and saves the result. Nothing is kept if the change fails.
+    pub fn update<F>(&self, change: F) -> Result<Workspace, GitError>
+    where
+        F: FnOnce(&mut Workspace) -> Result<(), GitError>,
+    {
+        let mut guard = self.lock();
+        let mut workspace = guard.clone();
+        change(&mut workspace)?;
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(file, serde_json::to_string_pretty(&workspace)?)?;
+        }
+        *guard = workspace.clone();
+        Ok(workspace)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Workspace> {
+        match self.workspace.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
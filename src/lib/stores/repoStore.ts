@@ -1,9 +1,11 @@
 import { invoke } from '@tauri-apps/api/core';
 import { get, writable } from 'svelte/store';
-import type { BranchInfo, CommitInfo, RepoInfo, RepoStatus } from '../types';
+import type { BatchAction, BatchSummary, BranchInfo, CommitInfo, PullResult, RepoInfo, RepoSettings, RepoStatus, Workspace } from '../types';
+import { errorMessage, isGitError } from '../utils';
 
 interface RepoState {
   repositories: RepoInfo[];
+  workspace: Workspace;
   selectedRepo: string | null;
   repoStatus: RepoStatus | null;
   commits: CommitInfo[];
@@ -12,9 +14,16 @@ interface RepoState {
   error: string | null;
 }
 
+// Sorts repositories in the order the workspace lists them.
+function orderedLike(repositories: RepoInfo[], workspace: Workspace): RepoInfo[] {
+  const order = workspace.repositories.map(r => r.path);
+  return [...repositories].sort((a, b) => order.indexOf(a.path) - order.indexOf(b.path));
+}
+
 function createRepoStore() {
   const { subscribe, set, update } = writable<RepoState>({
     repositories: [],
+    workspace: { groups: [], repositories: [], auto_fetch: { enabled: false, interval_secs: 600 } },
     selectedRepo: null,
     repoStatus: null,
     commits: [],
@@ -23,6 +32,18 @@ function createRepoStore() {
     error: null,
   });
 
+  // Only the selected repository is watched, the backend emits `repo-changed` for it.
+  function watchSelected(previous: string | null, next: string | null) {
+    if (previous && previous !== next) {
+      invoke('unwatch_repository_cmd', { path: previous })
+        .catch(error => console.log('[Store] Could not stop watching:', error));
+    }
+    if (next && next !== previous) {
+      invoke('watch_repository_cmd', { path: next })
+        .catch(error => console.log('[Store] Could not watch repository:', error));
+    }
+  }
+
   async function refreshRepository(path: string) {
     try {
       update(state => ({ ...state, loading: true, error: null }));
@@ -92,46 +113,129 @@ function createRepoStore() {
           return;
         }
         
-        const repoInfo = await invoke<RepoInfo>('get_repo_info_cmd', { path });
+        let repoInfo: RepoInfo;
+        try {
+          repoInfo = await invoke<RepoInfo>('get_repo_info_cmd', { path });
+        } catch (error) {
+          // Repositories owned by another user can be trusted through safe.directory
+          if (
+            !isGitError(error) ||
+            error.kind !== 'unsafe_repository' ||
+            !confirm(`${error.message}\n\nTrust this repository?`)
+          ) {
+            throw error;
+          }
+          await invoke<string>('add_safe_directory_cmd', { path });
+          repoInfo = await invoke<RepoInfo>('get_repo_info_cmd', { path });
+        }
+
+        // A folder inside a repository is added as the repository itself
+        if (repoInfo.root !== path) {
+          if (get({ subscribe }).repositories.some(r => r.path === repoInfo.root)) {
+            console.log('Repository already added:', repoInfo.root);
+            update(state => ({ ...state, loading: false }));
+            return;
+          }
+          repoInfo = { ...repoInfo, path: repoInfo.root };
+        }
+
+        const workspace = await invoke<Workspace>('add_workspace_repositories_cmd', {
+          paths: [repoInfo.path],
+        });
         update(state => ({
           ...state,
           repositories: [...state.repositories, repoInfo],
+          workspace,
           loading: false,
         }));
-        
-        // Save to localStorage
-        const repos = get({ subscribe }).repositories;
-        if (typeof window !== 'undefined') {
-          localStorage.setItem('repositories', JSON.stringify(repos.map(r => r.path)));
-        }
       } catch (error) {
         console.error('Failed to add repository:', error);
         update(state => ({
           ...state,
           loading: false,
-          error: error as string,
+          error: errorMessage(error),
         }));
         throw error; // Re-throw so the caller knows it failed
       }
     },
     
-    removeRepository: (path: string) => {
+    // Adds repositories found by scan_repositories_cmd, skipping ones already in the list.
+    // Returns how many were added.
+    importRepositories: async (found: RepoInfo[]) => {
+      const known = new Set(get({ subscribe }).repositories.map(r => r.path));
+      const added = found
+        .filter(info => !known.has(info.root))
+        .map(info => ({ ...info, path: info.root }));
+      if (added.length === 0) {
+        return 0;
+      }
+
+      const workspace = await invoke<Workspace>('add_workspace_repositories_cmd', {
+        paths: added.map(r => r.path),
+      });
       update(state => ({
         ...state,
-        repositories: state.repositories.filter(r => r.path !== path),
-        selectedRepo: state.selectedRepo === path ? null : state.selectedRepo,
+        repositories: [...state.repositories, ...added],
+        workspace,
       }));
-      
-      // Save to localStorage
-      const repos = get({ subscribe }).repositories;
-      if (typeof window !== 'undefined') {
-        localStorage.setItem('repositories', JSON.stringify(repos.map(r => r.path)));
+      return added.length;
+    },
+    
+    removeRepository: async (path: string) => {
+      try {
+        const workspace = await invoke<Workspace>('remove_workspace_repository_cmd', { path });
+        if (get({ subscribe }).selectedRepo === path) watchSelected(path, null);
+        update(state => ({
+          ...state,
+          repositories: state.repositories.filter(r => r.path !== path),
+          workspace,
+          selectedRepo: state.selectedRepo === path ? null : state.selectedRepo,
+        }));
+      } catch (error) {
+        update(state => ({ ...state, error: errorMessage(error) }));
+      }
+    },
+
+    updateWorkspaceRepository: async (
+      path: string,
+      alias: string | null,
+      group: string | null,
+      settings: RepoSettings,
+    ) => {
+      try {
+        const workspace = await invoke<Workspace>('update_workspace_repository_cmd', {
+          path,
+          alias,
+          group,
+          settings,
+        });
+        update(state => ({ ...state, workspace }));
+      } catch (error) {
+        update(state => ({ ...state, error: errorMessage(error) }));
+      }
+    },
+
+    reorderRepositories: async (paths: string[]) => {
+      try {
+        const workspace = await invoke<Workspace>('reorder_workspace_repositories_cmd', { paths });
+        update(state => ({
+          ...state,
+          repositories: orderedLike(state.repositories, workspace),
+          workspace,
+        }));
+      } catch (error) {
+        update(state => ({ ...state, error: errorMessage(error) }));
       }
     },
     
     selectRepository: async (path: string) => {
       console.log('[Store] Selecting repository:', path);
+      watchSelected(get({ subscribe }).selectedRepo, path);
       update(state => ({ ...state, selectedRepo: path, loading: true, error: null }));
+
+      invoke<Workspace>('mark_repository_opened_cmd', { path })
+        .then(workspace => update(state => ({ ...state, workspace })))
+        .catch(error => console.log('[Store] Could not record last opened:', error));
       
       let status: RepoStatus | null = null;
       let commits: CommitInfo[] = [];
@@ -183,7 +287,7 @@ function createRepoStore() {
         await refreshRepository(path);
       } catch (error) {
         console.error('[Store] Error staging file:', error);
-        update(state => ({ ...state, error: error as string }));
+        update(state => ({ ...state, error: errorMessage(error) }));
       }
     },
     
@@ -193,7 +297,7 @@ function createRepoStore() {
         await refreshRepository(path);
       } catch (error) {
         console.error('[Store] Error unstaging file:', error);
-        update(state => ({ ...state, error: error as string }));
+        update(state => ({ ...state, error: errorMessage(error) }));
       }
     },
     
@@ -202,14 +306,14 @@ function createRepoStore() {
         await invoke('commit_cmd', { path, message });
         await refreshRepository(path);
       } catch (error) {
-        update(state => ({ ...state, error: error as string }));
+        update(state => ({ ...state, error: errorMessage(error) }));
       }
     },
     
     pull: async (path: string) => {
       try {
         update(state => ({ ...state, loading: true, error: null }));
-        const result = await invoke<string>('pull_cmd', { path });
+        const result = await invoke<PullResult>('pull_cmd', { path });
         await refreshRepository(path);
         update(state => ({ ...state, loading: false }));
         return result;
@@ -217,7 +321,7 @@ function createRepoStore() {
         update(state => ({
           ...state,
           loading: false,
-          error: error as string,
+          error: errorMessage(error),
         }));
         throw error;
       }
@@ -234,7 +338,7 @@ function createRepoStore() {
         update(state => ({
           ...state,
           loading: false,
-          error: error as string,
+          error: errorMessage(error),
         }));
         throw error;
       }
@@ -250,31 +354,80 @@ function createRepoStore() {
         update(state => ({
           ...state,
           loading: false,
-          error: error as string,
+          error: errorMessage(error),
         }));
       }
     },
     
     loadRepositories: async () => {
       if (typeof window === 'undefined') return;
-      
+
+      let workspace = await invoke<Workspace>('get_workspace_cmd');
+
+      // Lists saved by earlier versions move into the workspace once
       const saved = localStorage.getItem('repositories');
-      if (!saved) return;
-      
-      const paths = JSON.parse(saved) as string[];
-      for (const path of paths) {
+      if (saved) {
+        if (workspace.repositories.length === 0) {
+          const paths = JSON.parse(saved) as string[];
+          workspace = await invoke<Workspace>('add_workspace_repositories_cmd', { paths });
+        }
+        localStorage.removeItem('repositories');
+      }
+      update(state => ({ ...state, workspace }));
+
+      for (const repo of workspace.repositories) {
+        if (repo.missing) {
+          console.log(`[Store] Repository no longer exists: ${repo.path}`);
+          continue;
+        }
         try {
-          const repoInfo = await invoke<RepoInfo>('get_repo_info_cmd', { path });
+          const repoInfo = await invoke<RepoInfo>('get_repo_info_cmd', { path: repo.path });
           update(state => ({
             ...state,
             repositories: [...state.repositories, repoInfo],
           }));
         } catch (error) {
-          console.error(`Failed to load repository ${path}:`, error);
+          console.error(`Failed to load repository ${repo.path}:`, error);
         }
       }
     },
     
+    // Runs fetch, fast-forward pull or status across the whole workspace and updates the
+    // sidebar from the summary.
+    runBatch: async (action: BatchAction, operationId?: string) => {
+      const summary = await invoke<BatchSummary>('batch_cmd', { action, operationId });
+      update(state => ({
+        ...state,
+        repositories: state.repositories.map(repo => {
+          const result = summary.results.find(r => r.path === repo.path);
+          if (!result || result.current_branch === null) return repo;
+          return {
+            ...repo,
+            current_branch: result.current_branch,
+            ahead: result.ahead,
+            behind: result.behind,
+            has_changes: result.has_changes,
+          };
+        }),
+      }));
+
+      const selected = get({ subscribe }).selectedRepo;
+      if (selected && action === 'pull') {
+        await refreshRepository(selected);
+      }
+      return summary;
+    },
+
+    // Takes the ahead/behind counts of a background fetch, see `remote-refs-changed`
+    applyRepoInfo: (repoInfo: RepoInfo) => {
+      update(state => ({
+        ...state,
+        repositories: state.repositories.map(r =>
+          r.path === repoInfo.path ? repoInfo : r
+        ),
+      }));
+    },
+
     clearError: () => {
       update(state => ({ ...state, error: null }));
     },
@@ -1,5 +1,6 @@
 export interface RepoInfo {
   path: string;
+  root: string;
   name: string;
   current_branch: string;
   has_changes: boolean;
@@ -12,6 +13,7 @@ export interface FileStatus {
   path: string;
   status: string;
   staged: boolean;
+  submodule: boolean;
 }
 
 export interface CommitInfo {
@@ -31,6 +33,17 @@ export interface RepoStatus {
   has_remote: boolean;
 }
 
+export interface PullResult {
+  status: 'up_to_date' | 'fast_forward' | 'merged' | 'rebased' | 'conflicts' | 'diverged';
+  strategy: 'merge' | 'rebase' | 'ff-only';
+  remote: string;
+  upstream: string;
+  old_head: string | null;
+  new_head: string | null;
+  conflicted_files: string[];
+  message: string;
+}
+
 export interface BranchInfo {
   name: string;
   is_current: boolean;
@@ -54,4 +67,228 @@ export interface FileDiff {
   new_content: string;
   patch: string;
   lines: DiffLine[];
+  submodule: SubmoduleChange | null;
+}
+
+export interface PushOptions {
+  remote: string | null;
+  branch: string | null;
+  destination: string | null;
+  force_with_lease: boolean;
+  set_upstream: boolean | null;
+}
+
+export interface PushRefStatus {
+  local_ref: string;
+  remote_ref: string;
+  status: 'ok' | 'non_fast_forward' | 'stale' | 'hook_declined' | 'rejected';
+  message: string | null;
+}
+
+export interface PushResult {
+  remote: string;
+  branch: string;
+  destination: string;
+  forced: boolean;
+  updates: PushRefStatus[];
+  message: string;
+}
+
+export interface RemoteInfo {
+  name: string;
+  url: string | null;
+  push_url: string | null;
+  fetch_refspecs: string[];
+  push_refspecs: string[];
+}
+
+export interface RemoteHeadInfo {
+  name: string;
+  id: string;
+  tracking_ref: string | null;
+  status: 'tracked' | 'new';
+}
+
+export interface RemoteDetails {
+  remote: RemoteInfo;
+  default_branch: string | null;
+  heads: RemoteHeadInfo[];
+  stale: string[];
+}
+
+export interface CloneOptions {
+  branch: string | null;
+  depth: number | null;
+  bare: boolean;
+  mirror: boolean;
+  recurse_submodules: boolean;
+  remote_name: string | null;
+}
+
+export interface InitOptions {
+  initial_branch: string | null;
+  bare: boolean;
+  gitignore: string[];
+  readme: boolean;
+  license: string | null;
+  initial_commit: boolean;
+  remote_url: string | null;
+  remote_name: string | null;
+}
+
+export interface InitTemplates {
+  gitignore: string[];
+  licenses: string[];
+}
+
+export type GitErrorKind =
+  | 'auth_required'
+  | 'auth_failed'
+  | 'not_a_repository'
+  | 'bare_repository'
+  | 'unsafe_repository'
+  | 'permission_denied'
+  | 'not_found'
+  | 'unborn_branch'
+  | 'conflicts'
+  | 'uncommitted_changes'
+  | 'non_fast_forward'
+  | 'locked_index'
+  | 'network'
+  | 'certificate'
+  | 'cancelled'
+  | 'invalid_input'
+  | 'other';
+
+export interface GitError {
+  kind: GitErrorKind;
+  message: string;
+  class: string | null;
+  code: string | null;
+}
+
+export interface CredentialRequest {
+  request_id: string;
+  operation_id: string;
+  url: string;
+  kind: 'userpass' | 'ssh_passphrase';
+  username: string | null;
+  key_path: string | null;
+  attempt: number;
+}
+
+export interface CredentialResponse {
+  username: string | null;
+  password: string | null;
+}
+
+export interface HostKeyRequest {
+  request_id: string;
+  operation_id: string;
+  host: string;
+  key_type: string;
+  fingerprint: string;
+}
+
+export interface HostKeyResponse {
+  decision: 'accept_once' | 'accept_always' | 'reject';
+}
+
+export interface SshSettings {
+  key_paths: string[];
+}
+
+export interface RepoSettings {
+  auto_fetch: boolean;
+  default_remote: string | null;
+}
+
+export interface WorkspaceRepo {
+  path: string;
+  alias: string | null;
+  group: string | null;
+  last_opened: number | null;
+  settings: RepoSettings;
+  missing: boolean;
+}
+
+export interface Workspace {
+  groups: string[];
+  repositories: WorkspaceRepo[];
+  auto_fetch: AutoFetchSettings;
+}
+
+export interface AutoFetchSettings {
+  enabled: boolean;
+  interval_secs: number;
+}
+
+export interface RefUpdate {
+  name: string;
+  remote: string;
+  kind: 'new' | 'updated' | 'deleted';
+  old_id: string | null;
+  new_id: string | null;
+}
+
+export interface RemoteRefsChangedEvent {
+  path: string;
+  updates: RefUpdate[];
+  repo_info: RepoInfo | null;
+}
+
+export type BatchAction = 'fetch' | 'pull' | 'status';
+
+export interface BatchProgressEvent {
+  operation_id: string;
+  path: string;
+  state: 'running' | 'done' | 'failed';
+  completed: number;
+  total: number;
+  message: string | null;
+}
+
+export interface BatchRepoResult {
+  path: string;
+  name: string;
+  current_branch: string | null;
+  ahead: number;
+  behind: number;
+  has_changes: boolean;
+  outcome: string | null;
+  error: GitError | null;
+}
+
+export interface BatchSummary {
+  action: BatchAction;
+  results: BatchRepoResult[];
+  succeeded: number;
+  failed: number;
+}
+
+export interface SubmoduleInfo {
+  name: string;
+  path: string;
+  url: string | null;
+  branch: string | null;
+  recorded_id: string | null;
+  staged_id: string | null;
+  checked_out_id: string | null;
+  state: 'uninitialized' | 'current' | 'moved';
+  dirty: boolean;
+}
+
+export interface SubmoduleChange {
+  path: string;
+  old_id: string | null;
+  new_id: string | null;
+  added: CommitInfo[];
+  removed: CommitInfo[];
+  dirty: boolean;
+  complete: boolean;
+}
+
+export interface RepoChangedEvent {
+  path: string;
+  kinds: ('worktree' | 'index' | 'head' | 'refs')[];
 }